@@ -0,0 +1,175 @@
+use serde_json::json;
+
+use crate::metric::Percentage;
+use crate::render::escape_xml;
+use crate::report::{Level, Thresholds};
+
+/// A shields-style coverage badge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    /// The text on the left side of the badge.
+    pub label: String,
+    /// The text on the right side of the badge.
+    pub message: String,
+    /// The color of the right side of the badge.
+    pub color: BadgeColor,
+}
+
+/// The color of the message of a [`Badge`], named as in shields.io.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeColor {
+    /// Used for low coverage.
+    Red,
+    /// Used for medium coverage.
+    Yellow,
+    /// Used for high coverage.
+    BrightGreen,
+    /// Used when the coverage is not applicable.
+    LightGrey,
+}
+
+impl BadgeColor {
+    /// Return the shields.io name of the color.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Yellow => "yellow",
+            Self::BrightGreen => "brightgreen",
+            Self::LightGrey => "lightgrey",
+        }
+    }
+
+    /// Return the color as a hex triplet, like `#4c1`.
+    pub fn hex(&self) -> &'static str {
+        match self {
+            Self::Red => "#e05d44",
+            Self::Yellow => "#dfb317",
+            Self::BrightGreen => "#4c1",
+            Self::LightGrey => "#9f9f9f",
+        }
+    }
+}
+
+impl Badge {
+    /// Create a badge for a coverage percentage, colored according to the thresholds.
+    pub fn new(label: impl Into<String>, percentage: Percentage, thresholds: &Thresholds) -> Self {
+        let message = match percentage {
+            Percentage::NotApplicable => "n/a".to_string(),
+            Percentage::Value(_) => format!("{percentage:.1}"),
+        };
+        let color = match thresholds.level(percentage) {
+            None => BadgeColor::LightGrey,
+            Some(Level::Low) => BadgeColor::Red,
+            Some(Level::Medium) => BadgeColor::Yellow,
+            Some(Level::High) => BadgeColor::BrightGreen,
+        };
+
+        Self {
+            label: label.into(),
+            message,
+            color,
+        }
+    }
+
+    /// Render the badge as an SVG image in the flat shields.io style.
+    pub fn svg(&self) -> String {
+        let label = escape_xml(&self.label);
+        let message = escape_xml(&self.message);
+
+        // The sides are padded by 5 pixels on both sides of the text.
+        let label_width = text_width(&self.label) + 10;
+        let message_width = text_width(&self.message) + 10;
+        let width = label_width + message_width;
+
+        // The text is scaled down by 10, which gives a finer position of the text.
+        let label_x = label_width * 5;
+        let message_x = label_width * 10 + message_width * 5;
+        let label_length = (label_width - 10) * 10;
+        let message_length = (message_width - 10) * 10;
+        let color = self.color.hex();
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+<title>{label}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" text-rendering="geometricPrecision" font-size="110">
+<text aria-hidden="true" x="{label_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{label_length}">{label}</text>
+<text x="{label_x}" y="140" transform="scale(.1)" fill="#fff" textLength="{label_length}">{label}</text>
+<text aria-hidden="true" x="{message_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{message_length}">{message}</text>
+<text x="{message_x}" y="140" transform="scale(.1)" fill="#fff" textLength="{message_length}">{message}</text>
+</g>
+</svg>
+"##
+        )
+    }
+
+    /// Render the badge as a shields.io endpoint JSON file.
+    ///
+    /// See <https://shields.io/badges/endpoint-badge> for the schema.
+    pub fn endpoint_json(&self) -> String {
+        let endpoint = json!({
+            "schemaVersion": 1,
+            "label": self.label,
+            "message": self.message,
+            "color": self.color.name(),
+        });
+        format!("{endpoint:#}\n")
+    }
+}
+
+/// Estimate the width in pixels of a text in 11px Verdana.
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '|' | '!' | '\'' => 4,
+            'f' | 'r' | 't' | 'I' | ' ' | '(' | ')' | '[' | ']' | '/' | '-' => 5,
+            'm' | 'w' | 'M' | 'W' | '%' => 11,
+            'A'..='Z' => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge() {
+        let thresholds = Thresholds::default();
+
+        let badge = Badge::new("coverage", Percentage::Value(79.07), &thresholds);
+        assert_eq!(badge.message, "79.1%");
+        assert_eq!(badge.color, BadgeColor::Yellow);
+
+        let badge = Badge::new("coverage", Percentage::Value(80.), &thresholds);
+        assert_eq!(badge.color, BadgeColor::BrightGreen);
+
+        let badge = Badge::new("functions", Percentage::NotApplicable, &thresholds);
+        assert_eq!(badge.message, "n/a");
+        assert_eq!(badge.color, BadgeColor::LightGrey);
+    }
+
+    #[test]
+    fn test_svg() {
+        let badge = Badge::new("a <b>", Percentage::Value(12.), &Thresholds::default());
+        let svg = badge.svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("<title>a &lt;b&gt;: 12.0%</title>"));
+        assert!(svg.contains(r##"fill="#e05d44""##));
+    }
+
+    #[test]
+    fn test_endpoint_json() {
+        let badge = Badge::new("coverage", Percentage::Value(90.), &Thresholds::default());
+        let value: serde_json::Value = serde_json::from_str(&badge.endpoint_json()).unwrap();
+
+        assert_eq!(value["schemaVersion"], 1);
+        assert_eq!(value["label"], "coverage");
+        assert_eq!(value["message"], "90.0%");
+        assert_eq!(value["color"], "brightgreen");
+    }
+}
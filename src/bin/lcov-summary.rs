@@ -1,42 +1,716 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
+use colored::Colorize;
+use regex::Regex;
 
-use lcov_summary::Lcov;
+use lcov_summary::render::{
+    Csv, Github, Json, Junit, LcovRenderer, Markdown, Renderer, Sarif, Terminal, Tracefile,
+};
+use lcov_summary::{
+    Badge, ChangedLines, ExclusionMarkers, History, HistoryEntry, Lcov, LcovSummary, Metric,
+    Ratchet, Report, Thresholds,
+};
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    lcov_file: std::path::PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The LCOV file, or `-` to read from stdin. Gzip and zstd files are decompressed.
+    #[arg(required = true)]
+    lcov_file: Option<PathBuf>,
     /// Only show the summary.
     #[arg(short, long)]
     full: bool,
+    /// Show the coverage per test name (`TN:` records), and the lines covered by each test only.
+    #[arg(long, conflicts_with = "diff_lcov_file")]
+    by_test: bool,
+    /// List the lines that are newly covered and newly uncovered in the second LCOV file. Not
+    /// supported by the `github`, `junit`, `sarif` and `lcov` formats.
+    #[arg(long, requires = "diff_lcov_file")]
+    compare: bool,
+    /// List the functions that were never called, with their source file and line. Not supported
+    /// by the `lcov` format.
+    #[arg(long, conflicts_with_all = ["diff_lcov_file", "by_test", "hotspots"])]
+    uncovered_functions: bool,
+    /// List the source files and functions whose uncovered lines would raise the total line
+    /// coverage the most. Not supported by the `github`, `sarif` and `lcov` formats.
+    #[arg(long, conflicts_with = "diff_lcov_file")]
+    hotspots: bool,
+    /// The number of source files and functions listed by `--hotspots`.
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "hotspots")]
+    top: usize,
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = Format::Terminal)]
+    format: Format,
+    /// Sort the source files, by path or by ascending coverage of a metric.
+    #[arg(long, value_enum)]
+    sort: Option<SortKey>,
+    /// Only include source files whose path matches this regex. Can be repeated.
+    #[arg(long, value_name = "REGEX")]
+    include: Vec<Regex>,
+    /// Leave out source files whose path matches this regex. Can be repeated.
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<Regex>,
+    /// Read every source file and leave out the code that is excluded by `LCOV_EXCL_*` markers.
+    #[arg(long)]
+    exclusion_markers: bool,
+    /// Also exclude the lines that match this regex. Implies `--exclusion-markers`. Can be
+    /// repeated.
+    #[arg(long, value_name = "REGEX")]
+    excl_line: Vec<Regex>,
+    /// Also exclude the lines from a line that matches this regex, like `coverage:off`. Implies
+    /// `--exclusion-markers`. Can be repeated.
+    #[arg(long, value_name = "REGEX")]
+    excl_start: Vec<Regex>,
+    /// Also exclude the lines up to a line that matches this regex, like `coverage:on`. Implies
+    /// `--exclusion-markers`. Can be repeated.
+    #[arg(long, value_name = "REGEX")]
+    excl_stop: Vec<Regex>,
+    /// Leave out the functions whose demangled name matches this regex, and their lines. Can be
+    /// repeated.
+    #[arg(long, value_name = "REGEX")]
+    exclude_fn: Vec<Regex>,
+    /// Only annotate uncovered lines that were added or changed in this unified diff, like the
+    /// output of `git diff`. Used by `--format github`.
+    #[arg(long, value_name = "DIFF")]
+    changed_lines: Option<PathBuf>,
+    #[command(flatten)]
+    thresholds: ThresholdArgs,
+    /// Keep running, and print the output again with what changed since the last run whenever an
+    /// LCOV file changes.
+    #[cfg(feature = "watch")]
+    #[arg(short, long)]
+    watch: bool,
+
+    diff_lcov_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a shields-style coverage badge as an SVG image.
+    Badge(BadgeArgs),
+    /// Append the coverage of a commit to a history file.
+    Record(RecordArgs),
+    /// Print the coverage over time from a history file, with sparklines per metric and per
+    /// directory.
+    Trend(TrendArgs),
+    /// Browse the coverage in a full-screen terminal UI.
+    #[cfg(feature = "tui")]
+    Tui(TuiArgs),
+    /// Check or raise the minimum coverage in a ratchet file.
+    Ratchet {
+        #[command(subcommand)]
+        command: RatchetCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum RatchetCommand {
+    /// Fail when any coverage dropped below its minimum in the ratchet file.
+    Check(RatchetArgs),
+    /// Raise the minimums in the ratchet file where the coverage improved.
+    Update {
+        #[command(flatten)]
+        args: RatchetArgs,
+        /// Also store the minimum coverage of every source file.
+        #[arg(long)]
+        per_file: bool,
+    },
+}
+
+#[derive(clap::Args)]
+struct RatchetArgs {
+    /// The LCOV file, or `-` to read from stdin. Gzip and zstd files are decompressed.
+    lcov_file: PathBuf,
+    /// The ratchet file. Source files are stored relative to the current directory.
+    #[arg(long, default_value = "coverage-ratchet.json")]
+    ratchet: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct BadgeArgs {
+    /// The LCOV file, or `-` to read from stdin. Gzip and zstd files are decompressed.
+    lcov_file: PathBuf,
+    /// The SVG file to write, or `-` for stdout.
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
+    /// The metric whose coverage is shown.
+    #[arg(long, value_enum, default_value_t = MetricArg::Lines)]
+    metric: MetricArg,
+    /// The text on the left side of the badge.
+    #[arg(long, default_value = "coverage")]
+    label: String,
+    /// Also write the badge as a shields.io endpoint JSON file.
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+    #[command(flatten)]
+    thresholds: ThresholdArgs,
+}
+
+#[derive(clap::Args)]
+struct RecordArgs {
+    /// The LCOV file, or `-` to read from stdin. Gzip and zstd files are decompressed.
+    lcov_file: PathBuf,
+    /// The commit that was measured.
+    #[arg(long)]
+    commit: String,
+    /// The date of the commit, in ISO 8601 format.
+    #[arg(long)]
+    date: String,
+    /// The history file, with an entry per line.
+    #[arg(long, default_value = HISTORY_FILE)]
+    history: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct TrendArgs {
+    /// The history file, with an entry per line.
+    #[arg(long, default_value = HISTORY_FILE)]
+    history: PathBuf,
+    /// The output format. The `github`, `sarif` and `lcov` formats are not supported.
+    #[arg(long, value_enum, default_value_t = Format::Terminal)]
+    format: Format,
+    #[command(flatten)]
+    thresholds: ThresholdArgs,
+}
+
+const HISTORY_FILE: &str = "coverage-history.jsonl";
+
+#[cfg(feature = "tui")]
+#[derive(clap::Args)]
+struct TuiArgs {
+    /// The LCOV file, or `-` to read from stdin. Gzip and zstd files are decompressed.
+    lcov_file: PathBuf,
+    #[command(flatten)]
+    thresholds: ThresholdArgs,
+}
+
+#[derive(clap::Args)]
+struct ThresholdArgs {
+    /// Coverage below this percentage is low.
+    #[arg(long, value_name = "PERCENT", default_value_t = Thresholds::default().low)]
+    low: f64,
+    /// Coverage below this percentage is medium, and high otherwise.
+    #[arg(long, value_name = "PERCENT", default_value_t = Thresholds::default().high)]
+    high: f64,
+}
+
+impl ThresholdArgs {
+    fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            low: self.low,
+            high: self.high,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// A table with colored percentages.
+    Terminal,
+    Json,
+    /// A GitHub flavored Markdown table.
+    Markdown,
+    /// Comma separated values, with a header line.
+    Csv,
+    /// Tab separated values, with a header line.
+    Tsv,
+    /// GitHub Actions annotations for the uncovered lines of the last LCOV file.
+    Github,
+    /// A JUnit XML test suite with a failing test case for every coverage below `--high`.
+    Junit,
+    /// A SARIF 2.1.0 log of the uncovered lines, functions and branches of the last LCOV file.
+    Sarif,
+    /// An LCOV tracefile of the last LCOV file, after filtering and exclusions.
+    Lcov,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MetricArg {
+    Lines,
+    Functions,
+    Branches,
+}
+
+impl MetricArg {
+    fn metric(self) -> Metric {
+        match self {
+            Self::Lines => Metric::Lines,
+            Self::Functions => Metric::Functions,
+            Self::Branches => Metric::Branches,
+        }
+    }
+}
 
-    diff_lcov_file: Option<std::path::PathBuf>,
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    Path,
+    Lines,
+    Functions,
+    Branches,
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    match run() {
+        // The reader of the output went away, like `head` does once it has enough lines.
+        Err(err)
+            if err
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| err.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
 
-    let lcov = Lcov::parse(args.lcov_file)?;
+fn run() -> Result<()> {
+    let mut args = Args::parse();
+    if args.hotspots && matches!(args.format, Format::Github | Format::Sarif | Format::Lcov) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--hotspots is not supported by the github, sarif and lcov formats",
+            )
+            .exit();
+    }
+    if args.compare
+        && matches!(
+            args.format,
+            Format::Github | Format::Junit | Format::Sarif | Format::Lcov
+        )
+    {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--compare is not supported by the github, junit, sarif and lcov formats",
+            )
+            .exit();
+    }
+    if args.uncovered_functions && matches!(args.format, Format::Lcov) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--uncovered-functions is not supported by the lcov format",
+            )
+            .exit();
+    }
 
-    let lcov2 = if let Some(file2) = args.diff_lcov_file {
-        Some(Lcov::parse(file2)?)
-    } else {
-        None
-    };
+    match args.command.take() {
+        Some(Command::Badge(args)) => return badge(args),
+        Some(Command::Record(args)) => {
+            let lcov = Lcov::parse(args.lcov_file)?;
+            let entry = HistoryEntry::new(args.commit, args.date, &lcov);
+            return History::append(&args.history, &entry);
+        }
+        Some(Command::Trend(args)) => {
+            if matches!(args.format, Format::Github | Format::Sarif | Format::Lcov) {
+                Args::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "trend is not supported by the github, sarif and lcov formats",
+                    )
+                    .exit();
+            }
+            let report = Report::trend(&History::parse(&args.history)?);
+            renderer(args.format, args.thresholds.thresholds())
+                .render(&report, &mut std::io::stdout().lock())?;
+            return Ok(());
+        }
+        #[cfg(feature = "tui")]
+        Some(Command::Tui(args)) => {
+            let lcov = Lcov::parse(args.lcov_file)?;
+            let root = std::env::current_dir().ok();
+            return lcov_summary::tui::run(lcov, root.as_deref(), args.thresholds.thresholds());
+        }
+        Some(Command::Ratchet { command }) => return ratchet(command),
+        None => {}
+    }
 
-    if args.full {
-        if let Some(lcov2) = lcov2 {
-            lcov.diffstd(&lcov2);
+    #[cfg(feature = "watch")]
+    if args.watch {
+        return watch::watch(&args);
+    }
+    summarize(&args)?;
+    Ok(())
+}
+
+/// Print the output for the LCOV files, and return the report to compare the next run of
+/// `--watch` with.
+///
+/// This is the printed report when it has metrics, and the source files of the last LCOV file
+/// otherwise.
+fn summarize(args: &Args) -> Result<Report> {
+    let mut names = vec![args
+        .lcov_file
+        .clone()
+        .expect("required without a subcommand")];
+    names.extend(args.diff_lcov_file.clone());
+
+    // Only the totals are printed, so the files are summarized without their test names.
+    if summary_only(args) {
+        let report = match &LcovSummary::parse_all(&names)?[..] {
+            [summary] => Report::summary(&names[0], summary),
+            [summary, other] => Report::diff_summary(&names[0], summary, &names[1], other),
+            _ => unreachable!(),
+        };
+        renderer(args.format, args.thresholds.thresholds())
+            .render(&report, &mut std::io::stdout().lock())?;
+        return Ok(report);
+    }
+
+    let mut lcovs = Lcov::parse_all(&names)?;
+
+    if args.exclusion_markers
+        || !args.excl_line.is_empty()
+        || !args.excl_start.is_empty()
+        || !args.excl_stop.is_empty()
+    {
+        let mut markers = ExclusionMarkers::default();
+        markers.line.extend(args.excl_line.iter().cloned());
+        markers.start.extend(args.excl_start.iter().cloned());
+        markers.stop.extend(args.excl_stop.iter().cloned());
+        for lcov in &mut lcovs {
+            for name in markers.apply(lcov)? {
+                eprintln!("warning: {name} does not exist, so its exclusion markers are ignored");
+            }
+        }
+    }
+
+    for lcov in &mut lcovs {
+        if !args.exclude_fn.is_empty() {
+            for file in lcov.files_mut() {
+                file.exclude_functions(|name| args.exclude_fn.iter().any(|re| re.is_match(name)));
+            }
+        }
+
+        lcov.retain(|file| {
+            (args.include.is_empty() || args.include.iter().any(|re| re.is_match(file.name())))
+                && !args.exclude.iter().any(|re| re.is_match(file.name()))
+        });
+
+        if let Some(key) = args.sort {
+            let metric = match key {
+                SortKey::Path => None,
+                SortKey::Lines => Some(Metric::Lines),
+                SortKey::Functions => Some(Metric::Functions),
+                SortKey::Branches => Some(Metric::Branches),
+            };
+            lcov.sort_by(|a, b| {
+                let by_metric = metric.map_or(std::cmp::Ordering::Equal, |metric| {
+                    a.metrics()
+                        .get(metric)
+                        .percentage()
+                        .cmp(&b.metrics().get(metric).percentage())
+                });
+                by_metric.then_with(|| a.name().cmp(b.name()))
+            });
+        }
+    }
+
+    let mut out = std::io::stdout().lock();
+
+    if args.compare {
+        let report = Report::compare(&lcovs[0], &lcovs[1]);
+        if report.groups.is_empty() && matches!(args.format, Format::Terminal) {
+            writeln!(out, "No differences in line coverage.")?;
         } else {
-            lcov.printstd();
+            renderer(args.format, args.thresholds.thresholds()).render(&report, &mut out)?;
         }
-    } else if let Some(lcov2) = lcov2 {
-        lcov.diffsummarystd(&lcov2);
-    } else {
-        lcov.summarystd();
+        return Ok(Report::files(&lcovs[1]));
+    }
+
+    if args.hotspots {
+        let report = Report::hotspots(&lcovs[0], args.top);
+        if !matches!(args.format, Format::Terminal) {
+            renderer(args.format, args.thresholds.thresholds()).render(&report, &mut out)?;
+        } else if let Some(top) = lcovs[0].hotspots().first() {
+            // The terminal also names the file that is worth covering first.
+            renderer(args.format, args.thresholds.thresholds()).render(&report, &mut out)?;
+            writeln!(out)?;
+            writeln!(
+                out,
+                "Covering {} fully adds {} to the line coverage.",
+                top.display_name().bold(),
+                format!("+{:.1}%", top.gain()).green()
+            )?;
+        } else {
+            writeln!(out, "All lines are covered.")?;
+        }
+        return Ok(Report::files(&lcovs[0]));
+    }
+
+    if let Format::Github = args.format {
+        let github = Github {
+            changed: args
+                .changed_lines
+                .as_deref()
+                .map(ChangedLines::parse)
+                .transpose()?,
+            root: std::env::current_dir().ok(),
+            only_functions: args.uncovered_functions,
+        };
+        github.render(lcovs.last().unwrap(), &mut out)?;
+        return Ok(Report::files(lcovs.last().unwrap()));
+    }
+
+    if let Format::Sarif = args.format {
+        let sarif = Sarif {
+            thresholds: args.thresholds.thresholds(),
+            root: std::env::current_dir().ok(),
+            only_functions: args.uncovered_functions,
+        };
+        sarif.render(lcovs.last().unwrap(), &mut out)?;
+        return Ok(Report::files(lcovs.last().unwrap()));
+    }
+
+    if let Format::Lcov = args.format {
+        Tracefile.render(lcovs.last().unwrap(), &mut out)?;
+        return Ok(Report::files(lcovs.last().unwrap()));
+    }
+
+    let report = match &lcovs[..] {
+        [lcov] if args.uncovered_functions => Report::uncovered_functions(lcov),
+        [lcov] if args.by_test => Report::tests(lcov),
+        [lcov] if args.full => Report::files(lcov),
+        // Machine readable output has a record for every file, also the unchanged ones.
+        [lcov, other] if args.full && matches!(args.format, Format::Csv | Format::Tsv) => {
+            Report::diff_all_files(lcov, other)
+        }
+        [lcov, other] if args.full => Report::diff_files(lcov, other),
+        [lcov] => Report::summary(lcov.name(), &lcov.summary()),
+        [lcov, other] => {
+            Report::diff_summary(lcov.name(), &lcov.summary(), other.name(), &other.summary())
+        }
+        _ => unreachable!(),
+    };
+
+    renderer(args.format, args.thresholds.thresholds()).render(&report, &mut out)?;
+
+    if report.metrics.is_empty() {
+        return Ok(Report::files(lcovs.last().unwrap()));
+    }
+    Ok(report)
+}
+
+/// Return `true` when only the totals of the LCOV files are printed, without filtering or
+/// excluding anything first.
+fn summary_only(args: &Args) -> bool {
+    !(args.full
+        || args.by_test
+        || args.compare
+        || args.uncovered_functions
+        || args.hotspots
+        || args.exclusion_markers
+        || !args.excl_line.is_empty()
+        || !args.excl_start.is_empty()
+        || !args.excl_stop.is_empty()
+        || !args.exclude_fn.is_empty()
+        || !args.include.is_empty()
+        || !args.exclude.is_empty()
+        || matches!(args.format, Format::Github | Format::Sarif | Format::Lcov))
+}
+
+/// Return the renderer of a report format.
+fn renderer(format: Format, thresholds: Thresholds) -> Box<dyn Renderer> {
+    match format {
+        Format::Terminal => Box::new(Terminal { thresholds }),
+        Format::Json => Box::new(Json),
+        Format::Markdown => Box::new(Markdown),
+        Format::Csv => Box::new(Csv {
+            root: std::env::current_dir().ok(),
+            ..Csv::default()
+        }),
+        Format::Tsv => Box::new(Csv {
+            root: std::env::current_dir().ok(),
+            ..Csv::tsv()
+        }),
+        Format::Junit => Box::new(Junit { thresholds }),
+        Format::Github | Format::Sarif | Format::Lcov => unreachable!(),
+    }
+}
+
+fn badge(args: BadgeArgs) -> Result<()> {
+    let summary = LcovSummary::parse(&args.lcov_file)?;
+    let badge = Badge::new(
+        args.label,
+        summary.percentage(args.metric.metric()),
+        &args.thresholds.thresholds(),
+    );
+
+    write_output(&args.output, &badge.svg())?;
+    if let Some(json) = args.json {
+        write_output(&json, &badge.endpoint_json())?;
     }
 
     Ok(())
 }
+
+/// Write to a file, or to stdout when the path is `-`.
+fn write_output(path: &Path, content: &str) -> Result<()> {
+    if path == Path::new("-") {
+        std::io::stdout().lock().write_all(content.as_bytes())?;
+    } else {
+        std::fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+fn ratchet(command: RatchetCommand) -> Result<()> {
+    let root = std::env::current_dir().ok();
+
+    match command {
+        RatchetCommand::Check(args) => {
+            let lcov = Lcov::parse(args.lcov_file)?;
+            let violations = Ratchet::parse(&args.ratchet)?.check(&lcov, root.as_deref());
+            let mut out = std::io::stdout().lock();
+            if violations.is_empty() {
+                writeln!(out, "No coverage dropped below the ratchet.")?;
+                return Ok(());
+            }
+
+            for violation in &violations {
+                writeln!(out, "{violation}")?;
+            }
+            anyhow::bail!(
+                "{} coverage values dropped below the ratchet",
+                violations.len()
+            );
+        }
+        RatchetCommand::Update { args, per_file } => {
+            let lcov = Lcov::parse(args.lcov_file)?;
+            let mut ratchet = Ratchet::parse(&args.ratchet)?;
+            ratchet.update(&lcov, root.as_deref(), per_file);
+            ratchet.write(&args.ratchet)
+        }
+    }
+}
+
+#[cfg(feature = "watch")]
+mod watch {
+    use std::io::IsTerminal;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use colored::Colorize;
+    use lcov_summary::Report;
+    use notify::event::{AccessKind, AccessMode};
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    use super::{summarize, Args};
+
+    /// Print the output again whenever an LCOV file changes, until the process is killed.
+    pub(crate) fn watch(args: &Args) -> Result<()> {
+        let mut files = vec![args
+            .lcov_file
+            .clone()
+            .expect("required without a subcommand")];
+        files.extend(args.diff_lcov_file.clone());
+        if files.iter().any(|file| file == Path::new("-")) {
+            anyhow::bail!("cannot watch stdin");
+        }
+        let files = files
+            .iter()
+            .map(|file| Ok(canonical(&std::path::absolute(file)?)))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        // Watch the directories instead of the files, because coverage tools replace the files.
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let mut directories: Vec<&Path> = files.iter().filter_map(|file| file.parent()).collect();
+        directories.dedup();
+        for directory in directories {
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+
+        let mut previous = None;
+        loop {
+            if std::io::stdout().is_terminal() {
+                // Clear the screen and move the cursor to the top left.
+                print!("\x1b[2J\x1b[H");
+            }
+
+            // A file that is being written can fail to parse, which is fixed by the next change.
+            match summarize(args) {
+                Ok(report) => {
+                    if let Some(previous) = &previous {
+                        print_changes(previous, &report);
+                    }
+                    previous = Some(report);
+                }
+                Err(err) => eprintln!("Error: {err:#}"),
+            }
+            eprintln!("\nWatching for changes, press Ctrl-C to stop.");
+
+            loop {
+                let event = rx.recv()??;
+                let written = !matches!(
+                    event.kind,
+                    EventKind::Access(
+                        AccessKind::Open(_) | AccessKind::Read | AccessKind::Close(_)
+                    )
+                ) || event.kind
+                    == EventKind::Access(AccessKind::Close(AccessMode::Write));
+                if written
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| files.contains(&canonical(path)))
+                {
+                    break;
+                }
+            }
+            // Wait until the writes settle, so the file is only parsed once it is complete.
+            while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        }
+    }
+
+    /// Return a path with the symlinks of its directory resolved, like `/tmp` on macOS, so the
+    /// watched files compare equal to the paths of the events.
+    ///
+    /// The file itself is not resolved, because it can be replaced or not exist yet.
+    fn canonical(path: &Path) -> PathBuf {
+        match (path.parent(), path.file_name()) {
+            (Some(directory), Some(name)) => std::fs::canonicalize(directory)
+                .unwrap_or_else(|_| directory.to_path_buf())
+                .join(name),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Print the coverage that changed since the previous run, in total and per row of the report.
+    fn print_changes(previous: &Report, report: &Report) {
+        let changes = report.changes_since(previous);
+        println!();
+        if changes.is_empty() {
+            println!("No changes since the last run.");
+            return;
+        }
+        println!("Since the last run:");
+        for change in changes {
+            let deltas: Vec<String> = change
+                .deltas
+                .iter()
+                .map(|&(metric, delta)| {
+                    let text = format!("{} {delta:+.2}%", metric.key());
+                    if delta > 0. {
+                        text.green().to_string()
+                    } else {
+                        text.red().to_string()
+                    }
+                })
+                .collect();
+            println!("  {}: {}", change.name, deltas.join(", "));
+        }
+    }
+}
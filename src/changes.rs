@@ -0,0 +1,187 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::BufRead;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::input;
+
+/// The lines that were added or changed in a unified diff, like the output of `git diff`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedLines {
+    files: HashMap<String, BTreeSet<usize>>,
+}
+
+impl ChangedLines {
+    /// Parse a unified diff, where `-` means stdin.
+    pub fn parse(name: &Path) -> Result<Self> {
+        Self::from_reader(input::open(name)?)
+    }
+
+    /// Parse a unified diff from a reader.
+    ///
+    /// Only the new side of the diff is kept: the lines that start with `+` in a hunk, numbered as
+    /// in the new version of the file. Deleted files are ignored.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut files: HashMap<String, BTreeSet<usize>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        // The next line number in the new file, and the number of lines left in the hunk.
+        let mut line = 0;
+        let mut old_remaining: usize = 0;
+        let mut new_remaining: usize = 0;
+
+        for text in reader.lines() {
+            let text = text?;
+
+            if old_remaining > 0 || new_remaining > 0 {
+                match text.chars().next() {
+                    Some('+') => {
+                        if let Some(file) = &current {
+                            files.entry(file.clone()).or_default().insert(line);
+                        }
+                        line += 1;
+                        new_remaining = new_remaining.saturating_sub(1);
+                    }
+                    Some('-') => old_remaining = old_remaining.saturating_sub(1),
+                    Some('\\') => {}
+                    _ => {
+                        line += 1;
+                        old_remaining = old_remaining.saturating_sub(1);
+                        new_remaining = new_remaining.saturating_sub(1);
+                    }
+                }
+            } else if let Some(path) = text.strip_prefix("+++ ") {
+                // Git quotes paths with special characters, and other tools append a timestamp.
+                let path = path.split('\t').next().unwrap_or(path).trim_matches('"');
+                current = match path {
+                    "/dev/null" => None,
+                    path => Some(path.strip_prefix("b/").unwrap_or(path).to_string()),
+                };
+            } else if let Some(hunk) = text.strip_prefix("@@ ") {
+                let (old, new) = hunk_ranges(hunk)?;
+                old_remaining = old;
+                (line, new_remaining) = new;
+            }
+        }
+
+        Ok(Self { files })
+    }
+
+    /// Return the changed lines of a source file.
+    ///
+    /// The paths in a diff are relative to the root of the repository, while LCOV files often
+    /// contain absolute paths. A path in the diff matches when it is equal to `name`, or when it
+    /// is a suffix of `name` that starts at a path separator. When several paths match, the
+    /// longest one wins. The path of the diff is returned together with the lines.
+    pub fn get(&self, name: &str) -> Option<(&str, &BTreeSet<usize>)> {
+        self.files
+            .iter()
+            .filter(|(path, _)| {
+                name == path.as_str()
+                    || name
+                        .strip_suffix(path.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('/'))
+            })
+            .max_by_key(|(path, _)| path.len())
+            .map(|(path, lines)| (path.as_str(), lines))
+    }
+
+    /// Return whether a line of a source file was changed.
+    pub fn contains(&self, name: &str, line: usize) -> bool {
+        self.get(name)
+            .is_some_and(|(_, lines)| lines.contains(&line))
+    }
+}
+
+/// Parse the ranges of a hunk header, like `-1,3 +1,4 @@`.
+///
+/// Returns the number of old lines, and the first new line and the number of new lines.
+fn hunk_ranges(hunk: &str) -> Result<(usize, (usize, usize))> {
+    let mut ranges = hunk.split(' ');
+    let old = ranges
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .ok_or_else(|| anyhow::anyhow!("invalid hunk header: @@ {hunk}"))?;
+    let new = ranges
+        .next()
+        .and_then(|range| range.strip_prefix('+'))
+        .ok_or_else(|| anyhow::anyhow!("invalid hunk header: @@ {hunk}"))?;
+
+    let (_, old_count) = range(old)?;
+    Ok((old_count, range(new)?))
+}
+
+/// Parse a range of a hunk header, like `5,3` or `5`, which is a single line.
+fn range(range: &str) -> Result<(usize, usize)> {
+    Ok(match range.split_once(',') {
+        Some((start, count)) => (start.parse()?, count.parse()?),
+        None => (range.parse()?, 1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_lines() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,4 @@
+ fn a() {
+-    old();
++    new();
++    newer();
+ }
+@@ -10 +11,2 @@ fn b() {
+-++x;
++++y;
++++z;
+diff --git a/src/gone.rs b/src/gone.rs
+deleted file mode 100644
+--- a/src/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+        let changed = ChangedLines::from_reader(diff.as_bytes()).unwrap();
+        let (path, lines) = changed.get("/home/ci/repo/src/a.rs").unwrap();
+        assert_eq!(path, "src/a.rs");
+        assert_eq!(lines.iter().copied().collect::<Vec<_>>(), [2, 3, 11, 12]);
+
+        assert!(changed.contains("src/a.rs", 11));
+        assert!(!changed.contains("src/a.rs", 1));
+        assert!(changed.get("/home/ci/repo/xsrc/a.rs").is_none());
+        assert!(changed.get("src/gone.rs").is_none());
+    }
+
+    #[test]
+    fn test_changed_lines_longest_suffix() {
+        let diff = "\
+--- a/lib.rs
++++ b/lib.rs
+@@ -1 +1 @@
+-a
++b
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -5 +5 @@
+-a
++b
+";
+
+        // Every map has its own iteration order, so parse the diff several times.
+        for _ in 0..8 {
+            let changed = ChangedLines::from_reader(diff.as_bytes()).unwrap();
+            let (path, lines) = changed.get("/home/ci/repo/src/lib.rs").unwrap();
+            assert_eq!(path, "src/lib.rs");
+            assert_eq!(lines.iter().copied().collect::<Vec<_>>(), [5]);
+            assert_eq!(changed.get("/home/ci/repo/lib.rs").unwrap().0, "lib.rs");
+        }
+    }
+}
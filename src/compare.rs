@@ -0,0 +1,88 @@
+use std::ops::RangeInclusive;
+
+/// The lines of a source file whose coverage differs between two LCOV files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileComparison {
+    pub(crate) name: String,
+    pub(crate) newly_covered: Vec<RangeInclusive<usize>>,
+    pub(crate) newly_uncovered: Vec<RangeInclusive<usize>>,
+}
+
+impl FileComparison {
+    /// Return the path of the source file.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the line ranges that are only covered by the other LCOV file.
+    pub fn newly_covered(&self) -> &[RangeInclusive<usize>] {
+        &self.newly_covered
+    }
+
+    /// Return the line ranges that are no longer covered by the other LCOV file.
+    pub fn newly_uncovered(&self) -> &[RangeInclusive<usize>] {
+        &self.newly_uncovered
+    }
+}
+
+/// Group the selected lines into ranges.
+///
+/// `lines` are all instrumented lines of a file in ascending order, together with whether they are
+/// selected. A range ends at the first instrumented line that is not selected, so lines without
+/// code (comments, blank lines) do not split a range.
+pub(crate) fn line_ranges(
+    lines: impl IntoIterator<Item = (usize, bool)>,
+) -> Vec<RangeInclusive<usize>> {
+    let mut ranges = vec![];
+    let mut current: Option<RangeInclusive<usize>> = None;
+
+    for (line, selected) in lines {
+        if selected {
+            current = Some(match current {
+                Some(range) => *range.start()..=line,
+                None => line..=line,
+            });
+        } else if let Some(range) = current.take() {
+            ranges.push(range);
+        }
+    }
+    ranges.extend(current);
+
+    ranges
+}
+
+/// Format line ranges as a comma separated list, for example `3-5, 9`.
+pub(crate) fn format_ranges(ranges: &[RangeInclusive<usize>]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            if range.start() == range.end() {
+                range.start().to_string()
+            } else {
+                format!("{}-{}", range.start(), range.end())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_ranges() {
+        let lines = [
+            (1, true),
+            (2, true),
+            (4, true),
+            (5, false),
+            (7, true),
+            (10, false),
+            (11, true),
+        ];
+
+        assert_eq!(line_ranges(lines), vec![1..=4, 7..=7, 11..=11]);
+        assert_eq!(format_ranges(&line_ranges(lines)), "1-4, 7, 11");
+    }
+}
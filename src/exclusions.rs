@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::{Lcov, LcovFile};
+
+/// The markers in source comments that exclude code from the coverage, like in lcov and genhtml.
+///
+/// Every field is a list of regexes that are matched against each line of a source file. The
+/// default markers are the standard ones of lcov.
+#[derive(Debug, Clone)]
+pub struct ExclusionMarkers {
+    /// Exclude the matching line, like `LCOV_EXCL_LINE`.
+    pub line: Vec<Regex>,
+    /// Exclude the lines from the matching line, like `LCOV_EXCL_START`.
+    pub start: Vec<Regex>,
+    /// Exclude the lines up to and including the matching line, like `LCOV_EXCL_STOP`.
+    pub stop: Vec<Regex>,
+    /// Exclude the branches on the matching line, like `LCOV_EXCL_BR_LINE`.
+    pub branch_line: Vec<Regex>,
+    /// Exclude the branches from the matching line, like `LCOV_EXCL_BR_START`.
+    pub branch_start: Vec<Regex>,
+    /// Exclude the branches up to and including the matching line, like `LCOV_EXCL_BR_STOP`.
+    pub branch_stop: Vec<Regex>,
+}
+
+impl Default for ExclusionMarkers {
+    fn default() -> Self {
+        let marker = |marker: &str| vec![Regex::new(marker).unwrap()];
+        Self {
+            line: marker("LCOV_EXCL_LINE"),
+            start: marker("LCOV_EXCL_START"),
+            stop: marker("LCOV_EXCL_STOP"),
+            branch_line: marker("LCOV_EXCL_BR_LINE"),
+            branch_start: marker("LCOV_EXCL_BR_START"),
+            branch_stop: marker("LCOV_EXCL_BR_STOP"),
+        }
+    }
+}
+
+/// The lines of a source file that are excluded by [`ExclusionMarkers`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExcludedLines {
+    /// The lines that are excluded completely.
+    pub lines: BTreeSet<usize>,
+    /// The lines whose branches are excluded.
+    pub branch_lines: BTreeSet<usize>,
+}
+
+impl ExclusionMarkers {
+    /// Find the excluded lines of a source file.
+    ///
+    /// Lines are numbered from 1. A region that is not stopped continues to the end of the file.
+    pub fn scan(&self, source: impl BufRead) -> io::Result<ExcludedLines> {
+        let matches = |regexes: &[Regex], text: &str| regexes.iter().any(|re| re.is_match(text));
+
+        let mut excluded = ExcludedLines::default();
+        let mut in_region = false;
+        let mut in_branch_region = false;
+
+        for (i, text) in source.split(b'\n').enumerate() {
+            let text = text?;
+            let text = String::from_utf8_lossy(&text);
+            let line = i + 1;
+
+            in_region |= matches(&self.start, &text);
+            if in_region || matches(&self.line, &text) {
+                excluded.lines.insert(line);
+            }
+            in_region &= !matches(&self.stop, &text);
+
+            in_branch_region |= matches(&self.branch_start, &text);
+            if in_branch_region || matches(&self.branch_line, &text) {
+                excluded.branch_lines.insert(line);
+            }
+            in_branch_region &= !matches(&self.branch_stop, &text);
+        }
+
+        Ok(excluded)
+    }
+
+    /// Remove the excluded lines, branches and functions of a source file, which is read from
+    /// the path of its `SF:` record.
+    ///
+    /// Relative paths are resolved from the current directory. A source file that does not exist
+    /// is left as it is, because LCOV files are often created on another machine, and `false` is
+    /// returned.
+    pub fn apply_file(&self, file: &mut LcovFile) -> Result<bool> {
+        let source = match File::open(Path::new(file.name())) {
+            Ok(source) => source,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+
+        let excluded = self.scan(BufReader::new(source))?;
+        file.exclude(&excluded.lines, &excluded.branch_lines);
+        Ok(true)
+    }
+
+    /// Remove the excluded lines, branches and functions of all source files of an LCOV file.
+    ///
+    /// Returns the paths of the source files that do not exist, and were left as they are.
+    pub fn apply(&self, lcov: &mut Lcov) -> Result<Vec<String>> {
+        let mut missing = vec![];
+        for file in lcov.files_mut() {
+            if !self.apply_file(file)? {
+                missing.push(file.name().to_string());
+            }
+        }
+        Ok(missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let source = "\
+fn a() {} // LCOV_EXCL_LINE
+fn b() {
+    // LCOV_EXCL_START
+    unreachable!();
+    // LCOV_EXCL_STOP
+}
+if x { y } // LCOV_EXCL_BR_LINE
+// coverage:off
+fn c() {}
+// coverage:on
+";
+        let mut markers = ExclusionMarkers::default();
+        markers.start.push(Regex::new("coverage:off").unwrap());
+        markers.stop.push(Regex::new("coverage:on").unwrap());
+
+        let excluded = markers.scan(source.as_bytes()).unwrap();
+        assert_eq!(
+            excluded.lines.into_iter().collect::<Vec<_>>(),
+            [1, 3, 4, 5, 8, 9, 10]
+        );
+        assert_eq!(excluded.branch_lines.into_iter().collect::<Vec<_>>(), [7]);
+    }
+
+    #[test]
+    fn test_exclude() {
+        let input = "\
+SF:src/a.rs
+FN:1,a
+FN:4,b
+FNDA:1,a
+FNDA:0,b
+FNF:2
+FNH:1
+DA:1,1
+DA:2,0
+DA:4,0
+DA:5,0
+LF:4
+LH:1
+BRDA:1,0,0,1
+BRDA:1,0,1,0
+BRDA:2,0,0,0
+BRF:3
+BRH:1
+end_of_record
+";
+        let mut lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let file = &mut lcov.files_mut()[0];
+        file.exclude(&BTreeSet::from([4, 5]), &BTreeSet::from([1]));
+
+        assert_eq!(file.function_hits().len(), 1);
+        assert_eq!(file.metrics().functions.percentage().value(), Some(100.));
+        assert_eq!(file.metrics().lines.found, 2);
+        assert_eq!(file.metrics().lines.hit, 1);
+        assert_eq!(file.metrics().branches.found, 1);
+        assert_eq!(file.metrics().branches.hit, 0);
+    }
+
+    #[test]
+    fn test_apply_missing_source() {
+        let input = "SF:/nonexistent/src/a.rs\nDA:1,0\nLF:1\nLH:0\nend_of_record\n";
+        let mut lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+
+        let missing = ExclusionMarkers::default().apply(&mut lcov).unwrap();
+        assert_eq!(missing, ["/nonexistent/src/a.rs"]);
+        assert_eq!(lcov.files()[0].metrics().lines.found, 1);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::lcov_file::display_name;
+use crate::metric::{Counter, Metric, Metrics, Percentage};
+use crate::Lcov;
+
+/// The coverage of a single commit in a [`History`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The commit that was measured.
+    pub commit: String,
+    /// The date of the commit, in ISO 8601 format.
+    pub date: String,
+    /// The totals of all source files.
+    pub metrics: Metrics,
+    /// The counters of every source file, keyed by path.
+    pub files: BTreeMap<String, Metrics>,
+}
+
+impl HistoryEntry {
+    /// Create an entry with the coverage of an LCOV file.
+    pub fn new(commit: impl Into<String>, date: impl Into<String>, lcov: &Lcov) -> Self {
+        Self {
+            commit: commit.into(),
+            date: date.into(),
+            metrics: lcov.summary().metrics,
+            files: lcov
+                .files()
+                .iter()
+                .map(|file| (file.name().to_string(), file.metrics))
+                .collect(),
+        }
+    }
+
+    /// Serialize the entry as a single line of JSON, without a newline.
+    pub fn to_json(&self) -> String {
+        let files: serde_json::Map<String, Value> = self
+            .files
+            .iter()
+            .map(|(name, metrics)| (name.clone(), metrics_to_json(metrics)))
+            .collect();
+
+        json!({
+            "commit": self.commit,
+            "date": self.date,
+            "metrics": metrics_to_json(&self.metrics),
+            "files": files,
+        })
+        .to_string()
+    }
+
+    /// Deserialize an entry from a line of JSON.
+    pub fn from_json(line: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(line)?;
+        let string = |key: &str| {
+            value[key]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| anyhow!("history entry without `{key}`"))
+        };
+
+        let mut files = BTreeMap::new();
+        if let Some(object) = value["files"].as_object() {
+            for (name, metrics) in object {
+                files.insert(name.clone(), metrics_from_json(metrics)?);
+            }
+        }
+
+        Ok(Self {
+            commit: string("commit")?,
+            date: string("date")?,
+            metrics: metrics_from_json(&value["metrics"])?,
+            files,
+        })
+    }
+
+    /// Return the totals of the source files per directory, keyed by the directory of their
+    /// display name.
+    pub fn directories(&self) -> BTreeMap<String, Metrics> {
+        let mut directories: BTreeMap<String, Metrics> = BTreeMap::new();
+        for (name, metrics) in &self.files {
+            let directory = match Path::new(display_name(name)).parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => {
+                    parent.to_string_lossy().to_string()
+                }
+                _ => ".".to_string(),
+            };
+            *directories.entry(directory).or_default() += *metrics;
+        }
+        directories
+    }
+}
+
+/// The coverage of a sequence of commits, stored as a JSON lines file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Parse a history file. A file that does not exist is an empty history.
+    pub fn parse(path: &Path) -> Result<Self> {
+        match File::open(path) {
+            Ok(file) => Self::from_reader(BufReader::new(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Parse a history from a reader, with an entry per line. Empty lines are skipped.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut entries = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(HistoryEntry::from_json(&line)?);
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Append an entry to a history file, which is created when it does not exist.
+    pub fn append(path: &Path, entry: &HistoryEntry) -> Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", entry.to_json())?;
+        Ok(())
+    }
+
+    /// Return the entries, in the order they were recorded.
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+}
+
+/// Draw percentages as a sparkline, scaled between the lowest and the highest percentage.
+///
+/// Percentages that are not applicable are drawn as a space.
+pub(crate) fn sparkline(percentages: &[Percentage]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let values = percentages.iter().filter_map(|p| p.value());
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+
+    percentages
+        .iter()
+        .map(|p| match p.value() {
+            None => ' ',
+            Some(_) if max == min => BARS[3],
+            Some(value) => BARS[((value - min) / (max - min) * 7.).round() as usize],
+        })
+        .collect()
+}
+
+fn metrics_to_json(metrics: &Metrics) -> Value {
+    let mut value = json!({});
+    for metric in Metric::ALL {
+        let counter = metrics.get(metric);
+        value[metric.key()] = json!({ "hit": counter.hit, "found": counter.found });
+    }
+    value
+}
+
+fn metrics_from_json(value: &Value) -> Result<Metrics> {
+    let mut metrics = Metrics::default();
+    for metric in Metric::ALL {
+        let counter = &value[metric.key()];
+        if counter.is_null() {
+            continue;
+        }
+        let count = |key: &str| {
+            counter[key]
+                .as_u64()
+                .map(|count| count as usize)
+                .ok_or_else(|| anyhow!("history entry without `{}.{key}`", metric.key()))
+        };
+        *metrics.get_mut(metric) = Counter {
+            hit: count("hit")?,
+            found: count("found")?,
+        };
+    }
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trip() {
+        let input = "\
+SF:/repo/src/iface/a.rs
+DA:1,1
+DA:2,0
+LF:2
+LH:1
+end_of_record
+SF:/repo/src/lib.rs
+DA:1,1
+LF:1
+LH:1
+end_of_record
+";
+        let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let entry = HistoryEntry::new("abc123", "2024-05-01T12:00:00Z", &lcov);
+
+        let line = entry.to_json();
+        assert!(!line.contains('\n'));
+        assert_eq!(HistoryEntry::from_json(&line).unwrap(), entry);
+
+        let history = History::from_reader(format!("{line}\n\n{line}\n").as_bytes()).unwrap();
+        assert_eq!(history.entries().len(), 2);
+
+        let directories = entry.directories();
+        assert_eq!(directories["src/iface"].lines, Counter { hit: 1, found: 2 });
+        assert_eq!(directories["src"].lines, Counter { hit: 1, found: 1 });
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let percentages = [
+            Percentage::Value(50.),
+            Percentage::Value(60.),
+            Percentage::NotApplicable,
+            Percentage::Value(85.),
+        ];
+        assert_eq!(sparkline(&percentages), "▁▃ █");
+        assert_eq!(sparkline(&[Percentage::Value(70.); 3]), "▄▄▄");
+    }
+}
@@ -0,0 +1,49 @@
+use crate::lcov_file::display_name;
+
+/// A source file or function with uncovered lines, and how much covering them would raise the
+/// total line coverage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotspot {
+    pub(crate) file: String,
+    pub(crate) function: Option<String>,
+    pub(crate) uncovered: usize,
+    pub(crate) gain: f64,
+}
+
+impl Hotspot {
+    /// Return the path of the source file.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Return the path of the source file, shortened for display.
+    pub fn display_name(&self) -> &str {
+        display_name(&self.file)
+    }
+
+    /// Return the demangled name of the function, or `None` for a whole source file.
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    /// Return the number of uncovered lines.
+    pub fn uncovered(&self) -> usize {
+        self.uncovered
+    }
+
+    /// Return the percentage points that the total line coverage would rise by when all
+    /// uncovered lines were covered.
+    pub fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+/// Sort hotspots by descending number of uncovered lines, then by file and function.
+pub(crate) fn rank(hotspots: &mut [Hotspot]) {
+    hotspots.sort_by(|a, b| {
+        b.uncovered
+            .cmp(&a.uncovered)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.function.cmp(&b.function))
+    });
+}
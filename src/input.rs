@@ -0,0 +1,90 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::Result;
+use flate2::bufread::MultiGzDecoder;
+
+/// The compression format of a tracefile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the magic bytes at the start of the reader, without
+    /// consuming them.
+    pub fn detect(reader: &mut impl BufRead) -> std::io::Result<Self> {
+        let magic = reader.fill_buf()?;
+        Ok(if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        })
+    }
+}
+
+/// Open a tracefile for reading, where `-` means stdin.
+pub fn open(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path == Path::new("-") {
+        Ok(Box::new(std::io::stdin().lock()))
+    } else {
+        let file = std::fs::File::open(path)?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Wrap a reader such that compressed input is decompressed transparently.
+pub fn decompress<'a>(mut reader: impl BufRead + 'a) -> Result<Box<dyn BufRead + 'a>> {
+    Ok(match Compression::detect(&mut reader)? {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lcov;
+    use std::io::Write;
+
+    const TRACEFILE: &str = "SF:src/lib.rs\nLF:4\nLH:3\nend_of_record\n";
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Compression::detect(&mut TRACEFILE.as_bytes()).unwrap(),
+            Compression::None
+        );
+        assert_eq!(
+            Compression::detect(&mut &[0x1f, 0x8b, 0x08][..]).unwrap(),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::detect(&mut &[0x28, 0xb5, 0x2f, 0xfd, 0x00][..]).unwrap(),
+            Compression::Zstd
+        );
+    }
+
+    #[test]
+    fn test_from_reader_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(TRACEFILE.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let lcov = Lcov::from_reader(&compressed[..]).unwrap();
+        assert_eq!(lcov.summary().metrics().lines.hit, 3);
+    }
+
+    #[test]
+    fn test_from_reader_zstd() {
+        let compressed = zstd::encode_all(TRACEFILE.as_bytes(), 0).unwrap();
+
+        let lcov = Lcov::from_reader(&compressed[..]).unwrap();
+        assert_eq!(lcov.summary().metrics().lines.found, 4);
+    }
+}
@@ -1,109 +1,128 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{self, BufRead};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use rayon::prelude::*;
 
-use crate::parsers::*;
+use crate::compare::{self, FileComparison};
+use crate::hotspot::{self, Hotspot};
+use crate::input;
+use crate::metric::{Counter, Metric, Metrics, Percentage};
+use crate::per_test::{TestCoverage, TestSummary};
+use crate::records::{self, Records};
+use crate::render::{Renderer, Terminal};
+use crate::report::Report;
 
+/// A parsed LCOV file.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Lcov {
-    name: std::path::PathBuf,
+    name: PathBuf,
     files: Vec<LcovFile>,
 }
 
+/// The totals of all files in an LCOV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LcovSummary {
-    total_lines: usize,
-    total_lines_hit: usize,
-    total_functions: usize,
-    total_functions_hit: usize,
+    pub(crate) metrics: Metrics,
 }
 
 impl LcovSummary {
-    fn lines_percentage(&self) -> f64 {
-        self.total_lines_hit as f64 / self.total_lines as f64 * 100.
+    /// Create a summary from the counters of all metrics.
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
     }
 
-    fn functions_percentage(&self) -> f64 {
-        self.total_functions_hit as f64 / self.total_functions as f64 * 100.
+    /// Summarize an LCOV file, where `-` means stdin.
+    ///
+    /// Records of the same source file are merged before they are counted.
+    pub fn parse(name: &Path) -> Result<Self> {
+        Self::from_reader(input::open(name)?)
     }
-}
 
-impl Lcov {
-    /// Parse an LCOV file.
-    pub fn parse(name: std::path::PathBuf) -> Result<Self> {
-        let source = std::fs::read_to_string(&name)?;
-        let mut files = vec![];
-        for line in source.lines() {
-            if line.starts_with("SF:") {
-                let (_, source) = source_file_path(line).unwrap();
-                files.push(LcovFile::new(&source));
-                continue;
-            }
+    /// Summarize an LCOV file from a reader.
+    ///
+    /// Records of the same source file are merged before they are counted, so the hits of every
+    /// source file are kept in memory until the end of the input. The test names of the records
+    /// are dropped.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut metrics = Metrics::default();
+        for file in merge_records(reader, false)? {
+            metrics += file.metrics;
+        }
+        Ok(Self { metrics })
+    }
 
-            if line.starts_with("FN:") {
-                let (_, (_, name)) = function_name(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.function_hits.insert(name.to_string(), 0);
-                }
-                continue;
-            }
+    /// Summarize multiple LCOV files in parallel.
+    ///
+    /// The summaries are returned in the order of `names`.
+    pub fn parse_all(names: &[PathBuf]) -> Result<Vec<Self>> {
+        names.par_iter().map(|name| Self::parse(name)).collect()
+    }
 
-            if line.starts_with("FNDA:") {
-                let (_, (hits, name)) = function_hit_count(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    *file.function_hits.get_mut(name).unwrap() = hits;
-                }
-                continue;
-            }
+    /// Return the counters of all metrics.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
 
-            if line.starts_with("FNF:") {
-                let (_, found) = functions_found(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.functions_found = found;
-                }
-                continue;
-            }
+    /// Print the summary to stdout.
+    pub fn printstd(&self, name: &Path) -> io::Result<()> {
+        print_report(&Report::summary(name, self))
+    }
 
-            if line.starts_with("FNH:") {
-                let (_, hit) = functions_hit(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.functions_hit = hit;
-                }
-                continue;
-            }
+    /// Print the diff of two summaries to stdout.
+    pub fn diffstd(&self, name: &Path, other: &Self, other_name: &Path) -> io::Result<()> {
+        print_report(&Report::diff_summary(name, self, other_name, other))
+    }
 
-            if line.starts_with("LF:") {
-                let (_, found) = lines_found(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.lines_found = found;
-                }
-                continue;
-            }
+    /// Return the percentage of hits for a metric.
+    pub fn percentage(&self, metric: Metric) -> Percentage {
+        self.metrics.get(metric).percentage()
+    }
+}
 
-            if line.starts_with("LH:") {
-                let (_, hit) = lines_hit(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.lines_hit = hit;
-                }
-                continue;
-            }
+impl Lcov {
+    /// Create an LCOV file from already parsed files.
+    pub fn new(name: PathBuf, files: Vec<LcovFile>) -> Self {
+        Self { name, files }
+    }
 
-            if line.starts_with("BF:") {
-                let (_, found) = branches_found(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.branches_found = found;
-                }
-                continue;
-            }
+    /// Parse an LCOV file, where `-` means stdin.
+    ///
+    /// Gzip and zstd compressed files are decompressed transparently.
+    pub fn parse(name: PathBuf) -> Result<Self> {
+        let mut lcov = Self::from_reader(input::open(&name)?)?;
+        lcov.name = name;
+        Ok(lcov)
+    }
 
-            if line.starts_with("BH:") {
-                let (_, hit) = branches_hit(line).unwrap();
-                if let Some(file) = files.last_mut() {
-                    file.branches_hit = hit;
-                }
-                continue;
-            }
-        }
+    /// Parse an LCOV file from a reader.
+    ///
+    /// The compression format is detected from the magic bytes at the start of the reader. Chunks
+    /// of records are parsed in parallel, the files keep the order of the input. Records of the
+    /// same source file, for example from different test names, are merged into a single file.
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        Ok(Self {
+            name: "-".into(),
+            files: merge_records(reader, true)?,
+        })
+    }
+
+    /// Parse multiple LCOV files in parallel.
+    ///
+    /// The parsed files are returned in the order of `names`.
+    pub fn parse_all(names: &[PathBuf]) -> Result<Vec<Self>> {
+        names
+            .par_iter()
+            .map(|name| Self::parse(name.clone()))
+            .collect()
+    }
 
-        Ok(Self { name, files })
+    /// Return the name of the LCOV file.
+    pub fn name(&self) -> &Path {
+        &self.name
     }
 
     /// Return a reference to the parsed files.
@@ -116,335 +135,890 @@ impl Lcov {
         &mut self.files
     }
 
-    pub fn diffstd(&self, other: &Self) {
-        todo!();
+    /// Keep only the files for which `f` returns `true`.
+    pub fn retain(&mut self, f: impl FnMut(&LcovFile) -> bool) {
+        self.files.retain(f);
     }
 
-    /// Print a summary of the diff of two files to stdout.
-    pub fn diffsummarystd(&self, other: &Self) {
-        use prettytable::{format::consts::FORMAT_CLEAN, format::Alignment, Cell, Row, Table};
-
-        let summary = self.summary();
-        let summary_other = other.summary();
-
-        let lines_diff = summary_other.lines_percentage() - summary.lines_percentage();
-        let functions_diff = summary_other.functions_percentage() - summary.functions_percentage();
-
-        let mut table = Table::new();
-        table.set_format(*FORMAT_CLEAN);
-        table.set_titles(Self::title_row());
-        table.add_row(Self::sub_title_row());
-
-        table.add_row(Row::new(vec![
-            Cell::new_align(&self.name.to_string_lossy(), Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_lines_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_lines.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(summary.lines_percentage(), 70., 80.),
-                Alignment::RIGHT,
-            ),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_functions_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_functions.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(summary.functions_percentage(), 70., 80.),
-                Alignment::RIGHT,
-            ),
-        ]));
-
-        table.add_row(Row::new(vec![
-            Cell::new_align(&other.name.to_string_lossy(), Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(&summary_other.total_lines_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary_other.total_lines.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(summary_other.lines_percentage(), 70., 80.),
-                Alignment::RIGHT,
-            ),
-            Cell::new("│"),
-            Cell::new_align(
-                &summary_other.total_functions_hit.to_string(),
-                Alignment::RIGHT,
-            ),
-            Cell::new_align(&summary_other.total_functions.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(summary_other.functions_percentage(), 70., 80.),
-                Alignment::RIGHT,
-            ),
-        ]));
-
-        table.add_row(Row::new(vec![
-            Cell::new_align("diff", Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(
-                {
-                    let diff =
-                        summary_other.total_lines_hit as isize - summary.total_lines_hit as isize;
-                    &match diff {
-                        diff if diff > 0 => format!("+ {diff}"),
-                        diff if diff < 0 => format!("- {}", diff.abs()),
-                        _ => String::new(),
-                    }
-                },
-                Alignment::RIGHT,
-            ),
-            Cell::new_align(
-                {
-                    let diff = summary_other.total_lines as isize - summary.total_lines as isize;
-                    &match diff {
-                        diff if diff > 0 => format!("+ {diff}"),
-                        diff if diff < 0 => format!("- {}", diff.abs()),
-                        _ => String::new(),
-                    }
-                },
-                Alignment::RIGHT,
-            ),
-            Cell::new_align(&Self::color_percentage_diff(lines_diff), Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(
-                {
-                    let diff = summary_other.total_functions_hit as isize
-                        - summary.total_functions_hit as isize;
-                    &match diff {
-                        diff if diff > 0 => format!("+ {diff}"),
-                        diff if diff < 0 => format!("- {}", diff.abs()),
-                        _ => String::new(),
-                    }
-                },
-                Alignment::RIGHT,
-            ),
-            Cell::new_align(
-                {
-                    let diff =
-                        summary_other.total_functions as isize - summary.total_functions as isize;
-                    &match diff {
-                        diff if diff > 0 => format!("+ {diff}"),
-                        diff if diff < 0 => format!("- {}", diff.abs()),
-                        _ => String::new(),
-                    }
-                },
-                Alignment::RIGHT,
-            ),
-            Cell::new_align(
-                &Self::color_percentage_diff(functions_diff),
-                Alignment::RIGHT,
-            ),
-        ]));
+    /// Sort the files with a comparator function, keeping the order of equal files.
+    pub fn sort_by(&mut self, compare: impl FnMut(&LcovFile, &LcovFile) -> Ordering) {
+        self.files.sort_by(compare);
+    }
 
-        table.printstd();
+    /// Print the diff of the files whose coverage differs in another LCOV file to stdout.
+    pub fn diffstd(&self, other: &Self) -> io::Result<()> {
+        print_report(&Report::diff_files(self, other))
+    }
+
+    /// Print a summary of the diff of two files to stdout.
+    pub fn diffsummarystd(&self, other: &Self) -> io::Result<()> {
+        self.summary()
+            .diffstd(&self.name, &other.summary(), &other.name)
     }
 
     /// Return the summary of a an LCOV file.
     pub fn summary(&self) -> LcovSummary {
-        let mut total_lines = 0;
-        let mut total_lines_hit = 0;
-
-        let mut total_functions = 0;
-        let mut total_functions_hit = 0;
+        let mut metrics = Metrics::default();
         for file in &self.files {
-            total_lines += file.lines_found;
-            total_lines_hit += file.lines_hit;
-            total_functions += file.functions_found;
-            total_functions_hit += file.functions_hit;
-        }
-        LcovSummary {
-            total_lines,
-            total_lines_hit,
-            total_functions,
-            total_functions_hit,
+            metrics += file.metrics;
         }
+        LcovSummary { metrics }
     }
 
-    /// Print the summary of an LCOV file to stdout.
-    pub fn summarystd(&self) {
-        use prettytable::{format::consts::FORMAT_CLEAN, format::Alignment, Cell, Row, Table};
-
-        let summary = self.summary();
-        let total_lines_p = summary.lines_percentage();
-        let total_functions_p = summary.functions_percentage();
-
-        let mut table = Table::new();
-        table.set_format(*FORMAT_CLEAN);
-        table.set_titles(Self::title_row());
-        table.add_row(Self::sub_title_row());
-
-        table.add_row(Row::new(vec![
-            Cell::new_align(&self.name.to_string_lossy(), Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_lines_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_lines.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(total_lines_p, 70., 80.),
-                Alignment::RIGHT,
-            ),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_functions_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_functions.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(total_functions_p, 70., 80.),
-                Alignment::RIGHT,
-            ),
-        ]));
-
-        table.printstd();
-    }
+    /// Return the coverage per test name, in the order the test names first appear.
+    pub fn test_summaries(&self) -> Vec<TestSummary> {
+        let mut summaries: Vec<TestSummary> = vec![];
+        for file in &self.files {
+            for test in &file.tests {
+                // A line is only covered by this test when all of its hits come from this test.
+                let unique_lines = test
+                    .line_hits
+                    .iter()
+                    .filter(|&(line, hits)| file.line_hits.get(line) == Some(hits))
+                    .count();
 
-    /// Print the LCOV file to stdout.
-    pub fn printstd(&self) {
-        use prettytable::{format::consts::FORMAT_CLEAN, format::Alignment, Cell, Row, Table};
+                let summary = match summaries.iter().position(|s| s.name == test.name) {
+                    Some(i) => &mut summaries[i],
+                    None => {
+                        summaries.push(TestSummary {
+                            name: test.name.clone(),
+                            metrics: Metrics::default(),
+                            unique_lines: 0,
+                        });
+                        summaries.last_mut().unwrap()
+                    }
+                };
+                summary.metrics += test.metrics;
+                summary.unique_lines += unique_lines;
+            }
+        }
+        summaries
+    }
 
-        let mut table = Table::new();
-        table.set_format(*FORMAT_CLEAN);
+    /// Print the coverage per test name to stdout.
+    pub fn testsummarystd(&self) -> io::Result<()> {
+        print_report(&Report::tests(self))
+    }
 
-        table.set_titles(Self::title_row());
-        table.add_row(Self::sub_title_row());
+    /// Compare the covered lines of every file with the covered lines in another LCOV file.
+    ///
+    /// Only files with differences are returned. Files that only exist in `other` come last.
+    pub fn compare(&self, other: &Self) -> Vec<FileComparison> {
+        let empty = BTreeMap::new();
+        let files: HashMap<&str, &LcovFile> =
+            self.files.iter().map(|f| (f.name.as_str(), f)).collect();
+        let other_files: HashMap<&str, &LcovFile> =
+            other.files.iter().map(|f| (f.name.as_str(), f)).collect();
 
-        let summary = self.summary();
-        let total_lines_p = summary.lines_percentage();
-        let total_functions_p = summary.functions_percentage();
+        let names = self.files.iter().map(|file| file.name.as_str()).chain(
+            other
+                .files
+                .iter()
+                .map(|file| file.name.as_str())
+                .filter(|name| !files.contains_key(name)),
+        );
 
-        for file in &self.files {
-            let lines_percentage = (file.lines_hit as f64 / file.lines_found as f64) * 100.;
+        let mut comparisons = vec![];
+        for name in names {
+            let lines = files.get(name).map_or(&empty, |file| &file.line_hits);
+            let other_lines = other_files.get(name).map_or(&empty, |file| &file.line_hits);
 
-            let functions_percentage =
-                (file.functions_hit as f64 / file.functions_found as f64) * 100.;
+            let mut all_lines: Vec<usize> =
+                lines.keys().chain(other_lines.keys()).copied().collect();
+            all_lines.sort_unstable();
+            all_lines.dedup();
 
-            let file_name = if let Some(i) = file.name.find("/src") {
-                file.name.split_at(i + 1).1
-            } else {
-                &file.name
+            let covered = |lines: &BTreeMap<usize, usize>, line| {
+                lines.get(&line).is_some_and(|&hits| hits > 0)
             };
 
-            table.add_row(Row::new(vec![
-                Cell::new(file_name),
-                Cell::new("│"),
-                Cell::new_align(&file.lines_hit.to_string(), Alignment::RIGHT),
-                Cell::new_align(&file.lines_found.to_string(), Alignment::RIGHT),
-                Cell::new_align(
-                    &Self::color_percentage(lines_percentage, 70., 80.),
-                    Alignment::RIGHT,
+            let comparison = FileComparison {
+                name: name.to_string(),
+                newly_covered: compare::line_ranges(
+                    all_lines
+                        .iter()
+                        .map(|&line| (line, !covered(lines, line) && covered(other_lines, line))),
                 ),
-                Cell::new("│"),
-                Cell::new_align(&file.functions_hit.to_string(), Alignment::RIGHT),
-                Cell::new_align(&file.functions_found.to_string(), Alignment::RIGHT),
-                Cell::new_align(
-                    &Self::color_percentage(functions_percentage, 70., 80.),
-                    Alignment::RIGHT,
+                newly_uncovered: compare::line_ranges(
+                    all_lines
+                        .iter()
+                        .map(|&line| (line, covered(lines, line) && !covered(other_lines, line))),
                 ),
-            ]));
-        }
+            };
 
-        table.add_row(Row::new(vec![
-            Cell::new_align(&self.name.to_string_lossy(), Alignment::RIGHT),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_lines_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_lines.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(total_lines_p, 70., 80.),
-                Alignment::RIGHT,
-            ),
-            Cell::new("│"),
-            Cell::new_align(&summary.total_functions_hit.to_string(), Alignment::RIGHT),
-            Cell::new_align(&summary.total_functions.to_string(), Alignment::RIGHT),
-            Cell::new_align(
-                &Self::color_percentage(total_functions_p, 70., 80.),
-                Alignment::RIGHT,
-            ),
-        ]));
-
-        table.printstd();
-    }
-
-    fn color_percentage_diff(value: f64) -> String {
-        use colored::*;
-
-        if value == 0. {
-            format!("= {value:.2}%").yellow().to_string()
-        } else if value > 0. {
-            format!("+ {value:.2}%").green().to_string()
-        } else {
-            let value = value.abs();
-            format!("- {value:.2}%").red().to_string()
+            if !comparison.newly_covered.is_empty() || !comparison.newly_uncovered.is_empty() {
+                comparisons.push(comparison);
+            }
         }
+        comparisons
     }
 
-    fn color_percentage(value: f64, low: f64, mid: f64) -> String {
-        use colored::*;
+    /// Return the source files with uncovered lines, ranked by their number of uncovered lines.
+    pub fn hotspots(&self) -> Vec<Hotspot> {
+        let found = self.summary().metrics.lines.found;
+        let mut hotspots: Vec<Hotspot> = self
+            .files
+            .iter()
+            .map(|file| {
+                let lines = file.metrics.lines;
+                hotspot(file, None, lines.found.saturating_sub(lines.hit), found)
+            })
+            .filter(|hotspot| hotspot.uncovered > 0)
+            .collect();
+        hotspot::rank(&mut hotspots);
+        hotspots
+    }
 
-        let p = format!("{value:.2}%");
-        format!(
-            "{}",
-            if value < low {
-                p.red()
-            } else if value < mid {
-                p.yellow()
-            } else {
-                p.green()
+    /// Return the functions with uncovered lines, ranked by their number of uncovered lines.
+    ///
+    /// The lines of a function are the lines from its `FN:` line up to its end line, when the
+    /// record has one, or else up to the next function or the end of the file. Source files
+    /// without `FN:` records have no function hotspots.
+    pub fn function_hotspots(&self) -> Vec<Hotspot> {
+        let found = self.summary().metrics.lines.found;
+        let mut hotspots = vec![];
+        for file in &self.files {
+            let starts: BTreeSet<usize> = file.function_lines.values().copied().collect();
+            for (name, &start) in &file.function_lines {
+                let end = file.function_end(name, start, &starts);
+                let uncovered = file
+                    .line_hits
+                    .range(start..=end)
+                    .filter(|(_, &hits)| hits == 0)
+                    .count();
+                if uncovered > 0 {
+                    let name = format!("{:#}", rustc_demangle::demangle(name));
+                    hotspots.push(hotspot(file, Some(name), uncovered, found));
+                }
             }
-        )
+        }
+        hotspot::rank(&mut hotspots);
+        hotspots
     }
 
-    fn title_row() -> prettytable::Row {
-        use prettytable::{format::Alignment, Cell, Row};
-
-        Row::new(vec![
-            Cell::new(""),
-            Cell::new(""),
-            {
-                let mut line = Cell::new_align("Lines", Alignment::CENTER);
-                line.set_hspan(3);
-                line
-            },
-            Cell::new(""),
-            {
-                let mut fs = Cell::new_align("Functions", Alignment::CENTER);
-                fs.set_hspan(3);
-                fs
-            },
-        ])
-    }
-    fn sub_title_row() -> prettytable::Row {
-        use prettytable::{Cell, Row};
-
-        Row::new(vec![
-            Cell::new(""),
-            Cell::new("│"),
-            Cell::new("Hit"),
-            Cell::new("Total"),
-            Cell::new("H/T"),
-            Cell::new("│"),
-            Cell::new("Hit"),
-            Cell::new("Total"),
-            Cell::new("H/T"),
-        ])
+    /// Print the summary of an LCOV file to stdout.
+    pub fn summarystd(&self) -> io::Result<()> {
+        self.summary().printstd(&self.name)
     }
+
+    /// Print the LCOV file to stdout.
+    pub fn printstd(&self) -> io::Result<()> {
+        print_report(&Report::files(self))
+    }
+}
+
+/// Parse the records of a reader in parallel, and merge the records of the same source file.
+///
+/// The files keep the order of the input. Without `tests`, the test names of the records are
+/// dropped.
+fn merge_records(reader: impl BufRead, tests: bool) -> Result<Vec<LcovFile>> {
+    let mut files: Vec<LcovFile> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    records::parse_parallel(
+        reader,
+        |chunk| {
+            let records = Records::new(chunk.as_bytes())?;
+            let records = if tests {
+                records
+            } else {
+                records.without_tests()
+            };
+            records.collect::<Result<Vec<_>>>()
+        },
+        |chunk| {
+            for file in chunk {
+                match index.get(&file.name) {
+                    Some(&i) => files[i].merge(file),
+                    None => {
+                        index.insert(file.name.clone(), files.len());
+                        files.push(file);
+                    }
+                }
+            }
+        },
+    )?;
+    Ok(files)
 }
 
-#[derive(Debug)]
+/// The coverage of a single source file.
+#[derive(Debug, Clone, PartialEq)]
 pub struct LcovFile {
-    name: String,
-    function_hits: HashMap<String, usize>,
-    functions_found: usize,
-    functions_hit: usize,
-    lines_found: usize,
-    lines_hit: usize,
-    branches_found: usize,
-    branches_hit: usize,
+    pub(crate) name: String,
+    /// The coverage per test name of the records that were merged into this file.
+    pub(crate) tests: Vec<TestCoverage>,
+    pub(crate) function_hits: HashMap<String, usize>,
+    /// The line of the definition of every function.
+    pub(crate) function_lines: HashMap<String, usize>,
+    /// The last line of every function, when the `FN:` or `FNL:` record has one.
+    pub(crate) function_end_lines: HashMap<String, usize>,
+    /// The other names of functions with several `FNA:` records for the same `FNL:` index.
+    pub(crate) function_aliases: HashMap<String, Vec<String>>,
+    /// The version or checksum of the source file, from the `VER:` record.
+    pub(crate) version: Option<String>,
+    pub(crate) line_hits: BTreeMap<usize, usize>,
+    /// Branch hits, keyed by line, block and branch number. `None` means that the block
+    /// containing the branch was never executed.
+    pub(crate) branch_hits: BTreeMap<(usize, String, String), Option<usize>>,
+    pub(crate) metrics: Metrics,
 }
 
 impl LcovFile {
+    /// Create a source file without any coverage.
     pub fn new(source: &impl AsRef<str>) -> Self {
         let source = source.as_ref();
         Self {
             name: source.to_string(),
+            tests: vec![],
             function_hits: Default::default(),
-            functions_found: 0,
-            functions_hit: 0,
-            lines_found: 0,
-            lines_hit: 0,
-            branches_found: 0,
-            branches_hit: 0,
+            function_lines: Default::default(),
+            function_end_lines: Default::default(),
+            function_aliases: Default::default(),
+            version: None,
+            line_hits: Default::default(),
+            branch_hits: Default::default(),
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Merge another record of the same source file into this one.
+    ///
+    /// The hit counts of functions, lines and branches are summed and the counters are recomputed
+    /// from them. Counters without detailed records are merged by taking the maximum, because
+    /// both records can cover the same code.
+    pub fn merge(&mut self, other: LcovFile) {
+        for test in other.tests {
+            match self.tests.iter_mut().find(|t| t.name == test.name) {
+                Some(t) => t.merge(test),
+                None => self.tests.push(test),
+            }
         }
+
+        // A function can be known under another of its aliases in the other record.
+        let mut functions: HashMap<String, String> = HashMap::new();
+        for (name, aliases) in &self.function_aliases {
+            for alias in aliases {
+                functions.insert(alias.clone(), name.clone());
+            }
+        }
+        for (name, hits) in other.function_hits {
+            let mut aliases = other
+                .function_aliases
+                .get(&name)
+                .cloned()
+                .unwrap_or_default();
+            aliases.insert(0, name.clone());
+            let function = aliases
+                .iter()
+                .find_map(|alias| {
+                    if self.function_hits.contains_key(alias) {
+                        Some(alias.clone())
+                    } else {
+                        functions.get(alias).cloned()
+                    }
+                })
+                .unwrap_or_else(|| name.clone());
+
+            *self.function_hits.entry(function.clone()).or_default() += hits;
+            if let Some(&line) = other.function_lines.get(&name) {
+                self.function_lines.entry(function.clone()).or_insert(line);
+            }
+            if let Some(&line) = other.function_end_lines.get(&name) {
+                self.function_end_lines
+                    .entry(function.clone())
+                    .or_insert(line);
+            }
+            for alias in aliases {
+                if alias == function
+                    || self
+                        .function_aliases
+                        .get(&function)
+                        .is_some_and(|known| known.contains(&alias))
+                {
+                    continue;
+                }
+                functions.insert(alias.clone(), function.clone());
+                self.function_aliases
+                    .entry(function.clone())
+                    .or_default()
+                    .push(alias);
+            }
+        }
+
+        if self.version.is_none() {
+            self.version = other.version;
+        }
+
+        for (line, hits) in other.line_hits {
+            *self.line_hits.entry(line).or_default() += hits;
+        }
+
+        for (branch, taken) in other.branch_hits {
+            let hits = self.branch_hits.entry(branch).or_default();
+            *hits = add_taken(*hits, taken);
+        }
+
+        for metric in Metric::ALL {
+            let counter = self.metrics.get_mut(metric);
+            let other = other.metrics.get(metric);
+            counter.found = counter.found.max(other.found);
+            counter.hit = counter.hit.max(other.hit);
+        }
+
+        self.recount(self.detailed_metrics());
+    }
+
+    /// Remove the coverage of excluded lines.
+    ///
+    /// The lines and branches on `lines` are removed, as are the functions that start on one of
+    /// `lines`. Only the branches are removed on `branch_lines`. The counters are recounted from
+    /// the remaining hit counts. For the coverage per test name, only the lines are recounted.
+    pub fn exclude(&mut self, lines: &BTreeSet<usize>, branch_lines: &BTreeSet<usize>) {
+        let functions: HashSet<String> = self
+            .function_lines
+            .iter()
+            .filter(|(_, line)| lines.contains(line))
+            .map(|(name, _)| name.clone())
+            .collect();
+        self.remove(lines, branch_lines, &functions);
+    }
+
+    /// Remove the functions for which `exclude` returns `true`, and their lines.
+    ///
+    /// `exclude` is called with the demangled name of every function and of its aliases, without
+    /// the hash suffix. The lines of a function are the lines from its `FN:` line up to its end
+    /// line, when the record has one, or else up to the next function that is not removed or the
+    /// end of the file. Only the matched functions are removed, and lines that also belong to a
+    /// function that is kept, like the line of a `#[derive]` with several traits, stay. The
+    /// counters are recounted as in [`Self::exclude`].
+    pub fn exclude_functions(&mut self, exclude: impl Fn(&str) -> bool) {
+        let excluded: HashSet<String> = self
+            .function_hits
+            .keys()
+            .filter(|&name| {
+                std::iter::once(name)
+                    .chain(self.function_aliases.get(name).into_iter().flatten())
+                    .any(|name| exclude(&format!("{:#}", rustc_demangle::demangle(name))))
+            })
+            .cloned()
+            .collect();
+        if excluded.is_empty() {
+            return;
+        }
+
+        let starts: BTreeSet<usize> = self.function_lines.values().copied().collect();
+        let kept_starts: BTreeSet<usize> = self
+            .function_lines
+            .iter()
+            .filter(|(name, _)| !excluded.contains(name.as_str()))
+            .map(|(_, &line)| line)
+            .collect();
+
+        let mut lines = BTreeSet::new();
+        let mut kept_lines = BTreeSet::new();
+        for (name, &start) in &self.function_lines {
+            if excluded.contains(name) {
+                lines.extend(start..=self.function_end(name, start, &kept_starts));
+            } else {
+                kept_lines.extend(start..=self.function_end(name, start, &starts));
+            }
+        }
+        let lines: BTreeSet<usize> = lines.difference(&kept_lines).copied().collect();
+
+        self.remove(&lines, &BTreeSet::new(), &excluded);
+    }
+
+    /// Remove the coverage of lines, of branches on `branch_lines` and of `functions`.
+    fn remove(
+        &mut self,
+        lines: &BTreeSet<usize>,
+        branch_lines: &BTreeSet<usize>,
+        functions: &HashSet<String>,
+    ) {
+        let detailed = self.detailed_metrics();
+
+        let function_lines = &mut self.function_lines;
+        let function_end_lines = &mut self.function_end_lines;
+        let function_aliases = &mut self.function_aliases;
+        self.function_hits.retain(|name, _| {
+            let excluded = functions.contains(name);
+            if excluded {
+                function_lines.remove(name);
+                function_end_lines.remove(name);
+                function_aliases.remove(name);
+            }
+            !excluded
+        });
+        self.line_hits.retain(|line, _| !lines.contains(line));
+        self.branch_hits
+            .retain(|(line, _, _), _| !lines.contains(line) && !branch_lines.contains(line));
+
+        self.recount(detailed);
+
+        for test in &mut self.tests {
+            test.line_hits.retain(|line, _| !lines.contains(line));
+            test.metrics.lines = Counter {
+                hit: test.line_hits.len(),
+                found: test.metrics.lines.found.min(self.metrics.lines.found),
+            };
+        }
+    }
+
+    /// Return the last line of a function that starts on `start`, which is its end line when the
+    /// record has one, or else the line before the next line of `starts` or the last instrumented
+    /// line.
+    fn function_end(&self, name: &str, start: usize, starts: &BTreeSet<usize>) -> usize {
+        if let Some(&end) = self.function_end_lines.get(name) {
+            return end.max(start);
+        }
+
+        let last_line = self.line_hits.keys().last().copied().unwrap_or(0);
+        starts
+            .range(start + 1..)
+            .next()
+            .map_or(last_line, |&next| next - 1)
+            .max(start)
+    }
+
+    /// Return the metrics whose hit counts are recorded per function, line or branch.
+    fn detailed_metrics(&self) -> Vec<Metric> {
+        let mut metrics = vec![];
+        if !self.function_hits.is_empty() {
+            metrics.push(Metric::Functions);
+        }
+        if !self.line_hits.is_empty() {
+            metrics.push(Metric::Lines);
+        }
+        if !self.branch_hits.is_empty() {
+            metrics.push(Metric::Branches);
+        }
+        metrics
+    }
+
+    /// Recount the counters of metrics from the hit counts of the functions, lines and branches.
+    ///
+    /// Only metrics from [`Self::detailed_metrics`] should be recounted, the counters of the other
+    /// metrics come from the `FNF`/`FNH`, `LF`/`LH` and `BRF`/`BRH` records.
+    fn recount(&mut self, metrics: Vec<Metric>) {
+        for metric in metrics {
+            let counter = match metric {
+                Metric::Functions => Counter {
+                    hit: self
+                        .function_hits
+                        .values()
+                        .filter(|&&hits| hits > 0)
+                        .count(),
+                    found: self.function_hits.len(),
+                },
+                Metric::Lines => Counter {
+                    hit: self.line_hits.values().filter(|&&hits| hits > 0).count(),
+                    found: self.line_hits.len(),
+                },
+                Metric::Branches => Counter {
+                    hit: self
+                        .branch_hits
+                        .values()
+                        .filter(|&&taken| taken.unwrap_or(0) > 0)
+                        .count(),
+                    found: self.branch_hits.len(),
+                },
+            };
+            *self.metrics.get_mut(metric) = counter;
+        }
+    }
+
+    pub(crate) fn display_name(&self) -> &str {
+        display_name(&self.name)
+    }
+
+    /// Recount the counters of a finished record from its hit counts, and attribute its coverage
+    /// to its test name.
+    ///
+    /// The counters are recounted like in [`Self::merge`], so a record gives the same numbers on
+    /// its own as when it is merged, whether or not it has `FNF`, `LF` or `BRF` records.
+    pub(crate) fn finish_record(&mut self) {
+        self.recount(self.detailed_metrics());
+        for test in &mut self.tests {
+            test.metrics = self.metrics;
+            test.line_hits = self
+                .line_hits
+                .iter()
+                .filter(|(_, &hits)| hits > 0)
+                .map(|(&line, &hits)| (line, hits))
+                .collect();
+        }
+    }
+
+    /// Return the path of the source file, as it appears in the `SF:` record.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the hit count of every function, keyed by its mangled name.
+    pub fn function_hits(&self) -> &HashMap<String, usize> {
+        &self.function_hits
+    }
+
+    /// Return the line of the definition of every function, keyed by its mangled name.
+    pub fn function_lines(&self) -> &HashMap<String, usize> {
+        &self.function_lines
+    }
+
+    /// Return the demangled names of the functions that were never called, with the line of
+    /// their `FN:` record.
+    ///
+    /// The functions are sorted by line. Functions without an `FN:` record have no line and come
+    /// last.
+    pub fn uncovered_functions(&self) -> Vec<(Option<usize>, String)> {
+        let mut functions: Vec<(Option<usize>, String)> = self
+            .function_hits
+            .iter()
+            .filter(|&(_, &hits)| hits == 0)
+            .map(|(name, _)| {
+                let line = self.function_lines.get(name).copied();
+                (line, format!("{:#}", rustc_demangle::demangle(name)))
+            })
+            .collect();
+        functions.sort_by(|a, b| (a.0.is_none(), a).cmp(&(b.0.is_none(), b)));
+        functions
+    }
+
+    /// Return the last line of every function whose `FN:` or `FNL:` record has one, keyed by its
+    /// mangled name.
+    pub fn function_end_lines(&self) -> &HashMap<String, usize> {
+        &self.function_end_lines
+    }
+
+    /// Return the other names of functions that have several `FNA:` records for the same `FNL:`
+    /// index in LCOV 2.x, keyed by the name that the function is counted under.
+    pub fn function_aliases(&self) -> &HashMap<String, Vec<String>> {
+        &self.function_aliases
+    }
+
+    /// Return the version or checksum of the source file, from the `VER:` record of LCOV 2.x.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Return the hit count of every instrumented line.
+    pub fn line_hits(&self) -> &BTreeMap<usize, usize> {
+        &self.line_hits
+    }
+
+    /// Return the number of times every branch was taken, keyed by line, block and branch.
+    ///
+    /// The block and branch are kept as they are written in the `BRDA:` record, like `e0` for the
+    /// block of an exception branch of LCOV 2.x. `None` means that the block containing the
+    /// branch was never executed.
+    pub fn branch_hits(&self) -> &BTreeMap<(usize, String, String), Option<usize>> {
+        &self.branch_hits
+    }
+
+    /// Return the ranges of uncovered lines, only including the lines for which `include`
+    /// returns `true`.
+    ///
+    /// A range ends at the first instrumented line that is covered or not included, so lines
+    /// without code do not split a range.
+    pub fn uncovered_ranges(&self, include: impl Fn(usize) -> bool) -> Vec<RangeInclusive<usize>> {
+        compare::line_ranges(
+            self.line_hits
+                .iter()
+                .map(|(&line, &hits)| (line, hits == 0 && include(line))),
+        )
+    }
+
+    /// Return the coverage per test name of the records of this file.
+    pub fn tests(&self) -> &[TestCoverage] {
+        &self.tests
+    }
+
+    /// Return the counters of all metrics.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Return a mutable reference to the counters of all metrics.
+    pub fn metrics_mut(&mut self) -> &mut Metrics {
+        &mut self.metrics
+    }
+}
+
+/// Return the name of a source file to display, which is its path relative to the `src` directory
+/// when possible.
+pub(crate) fn display_name(name: &str) -> &str {
+    if let Some(i) = name.find("/src") {
+        name.split_at(i + 1).1
+    } else {
+        name
+    }
+}
+
+/// Create a hotspot of a source file, or of one of its functions.
+fn hotspot(file: &LcovFile, function: Option<String>, uncovered: usize, found: usize) -> Hotspot {
+    Hotspot {
+        file: file.name.clone(),
+        function,
+        uncovered,
+        gain: uncovered as f64 / found.max(1) as f64 * 100.,
+    }
+}
+
+/// Add the number of times a branch was taken, where `None` means that its block was not executed.
+pub(crate) fn add_taken(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Print a report as a table to stdout.
+fn print_report(report: &Report) -> io::Result<()> {
+    Terminal::default().render(report, &mut io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Field;
+
+    #[test]
+    fn test_merge_duplicate_records() {
+        let input = "\
+TN:unit
+SF:src/a.rs
+FN:1,foo
+FN:5,bar
+FNDA:1,foo
+FNDA:0,bar
+FNF:2
+FNH:1
+DA:1,1
+DA:2,0
+DA:5,0
+LF:3
+LH:1
+end_of_record
+TN:integration
+SF:src/a.rs
+FN:1,foo
+FN:5,bar
+FNDA:0,foo
+FNDA:2,bar
+FNF:2
+FNH:1
+DA:1,0
+DA:2,0
+DA:5,2
+LF:3
+LH:1
+end_of_record
+";
+
+        let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(lcov.files().len(), 1);
+
+        let file = &lcov.files()[0];
+        let test_names: Vec<_> = file.tests().iter().map(|t| t.name()).collect();
+        assert_eq!(test_names, ["unit", "integration"]);
+        assert_eq!(file.metrics().lines, Counter { hit: 2, found: 3 });
+        assert_eq!(file.metrics().functions, Counter { hit: 2, found: 2 });
+
+        let summary = lcov.summary();
+        assert_eq!(summary.metrics().lines, Counter { hit: 2, found: 3 });
+        let streamed = LcovSummary::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(streamed, summary);
+
+        let tests = lcov.test_summaries();
+        assert_eq!(tests[0].name(), "unit");
+        assert_eq!(tests[0].metrics().lines, Counter { hit: 1, found: 3 });
+        assert_eq!(tests[0].unique_lines(), 1);
+        assert_eq!(tests[1].name(), "integration");
+        assert_eq!(tests[1].unique_lines(), 1);
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = "SF:src/a.rs\nDA:1,1\nDA:2,1\nDA:4,0\nDA:5,0\nDA:6,1\nend_of_record\n";
+        let b = "SF:src/a.rs\nDA:1,1\nDA:2,0\nDA:4,3\nDA:5,1\nDA:6,1\nend_of_record\n\
+                 SF:src/b.rs\nDA:1,1\nend_of_record\n";
+
+        let a = Lcov::from_reader(a.as_bytes()).unwrap();
+        let b = Lcov::from_reader(b.as_bytes()).unwrap();
+
+        let comparisons = a.compare(&b);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].name(), "src/a.rs");
+        assert_eq!(comparisons[0].newly_covered(), [4..=5]);
+        assert_eq!(comparisons[0].newly_uncovered(), [2..=2]);
+        assert_eq!(comparisons[1].name(), "src/b.rs");
+        assert_eq!(comparisons[1].newly_covered(), [1..=1]);
+
+        assert!(a.compare(&a).is_empty());
+    }
+
+    #[test]
+    fn test_exclude_functions() {
+        let input = "\
+SF:src/a.rs
+FN:1,_RNvXCs123_4testNtB2_1SNtNtCs456_4core3fmt5Debug3fmt
+FN:5,_RNvCs123_4test3foo
+FNDA:0,_RNvXCs123_4testNtB2_1SNtNtCs456_4core3fmt5Debug3fmt
+FNDA:1,_RNvCs123_4test3foo
+FNF:2
+FNH:1
+DA:1,0
+DA:2,0
+DA:5,1
+DA:6,0
+LF:4
+LH:1
+end_of_record
+";
+        let mut lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let file = &mut lcov.files_mut()[0];
+        file.exclude_functions(|name| name.contains("Debug"));
+
+        assert_eq!(file.function_hits().len(), 1);
+        assert_eq!(file.metrics().functions, Counter { hit: 1, found: 1 });
+        assert_eq!(file.line_hits().keys().copied().collect::<Vec<_>>(), [5, 6]);
+        assert_eq!(file.metrics().lines, Counter { hit: 1, found: 2 });
+    }
+
+    #[test]
+    fn test_exclude_functions_same_line() {
+        // `#[derive(Debug, Clone)]` puts both functions on the line of the attribute.
+        let input = "\
+SF:src/a.rs
+FN:1,_RNvXCs123_4testNtB2_1SNtNtCs456_4core3fmt5Debug3fmt
+FN:1,_RNvXCs123_4testNtB2_1SNtNtCs456_4core5clone5Clone5clone
+FN:3,_RNvCs123_4test3foo
+FNDA:0,_RNvXCs123_4testNtB2_1SNtNtCs456_4core3fmt5Debug3fmt
+FNDA:1,_RNvXCs123_4testNtB2_1SNtNtCs456_4core5clone5Clone5clone
+FNDA:1,_RNvCs123_4test3foo
+FNF:3
+FNH:2
+DA:1,1
+DA:3,1
+DA:4,0
+LF:3
+LH:2
+end_of_record
+";
+        let mut lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let file = &mut lcov.files_mut()[0];
+        file.exclude_functions(|name| name.contains("Debug"));
+
+        let mut names: Vec<_> = file.function_hits().keys().cloned().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "_RNvCs123_4test3foo",
+                "_RNvXCs123_4testNtB2_1SNtNtCs456_4core5clone5Clone5clone",
+            ]
+        );
+        assert_eq!(file.metrics().functions, Counter { hit: 2, found: 2 });
+        assert_eq!(
+            file.line_hits().keys().copied().collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+        assert_eq!(file.metrics().lines, Counter { hit: 2, found: 3 });
+    }
+
+    #[test]
+    fn test_exclude_functions_end_lines() {
+        let input = "\
+SF:src/a.rs
+FN:1,2,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3foo
+FNF:1
+FNH:0
+DA:1,0
+DA:2,0
+DA:3,1
+LF:3
+LH:1
+end_of_record
+";
+        let mut lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let file = &mut lcov.files_mut()[0];
+        file.exclude_functions(|name| name == "test::foo");
+
+        assert!(file.function_end_lines().is_empty());
+        assert_eq!(file.line_hits().keys().copied().collect::<Vec<_>>(), [3]);
+        assert_eq!(file.metrics().lines, Counter { hit: 1, found: 1 });
+    }
+
+    #[test]
+    fn test_hotspots() {
+        let input = "\
+SF:src/a.rs
+FN:1,_RNvCs123_4test3foo
+FN:5,_RNvCs123_4test3bar
+FNDA:1,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3bar
+FNF:2
+FNH:1
+DA:1,1
+DA:2,0
+DA:5,0
+DA:6,0
+LF:4
+LH:1
+end_of_record
+SF:src/b.rs
+DA:1,1
+DA:2,0
+LF:2
+LH:1
+end_of_record
+SF:src/c.rs
+DA:1,1
+LF:1
+LH:1
+end_of_record
+";
+        let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+
+        let hotspots = lcov.hotspots();
+        assert_eq!(hotspots.len(), 2);
+        assert_eq!(hotspots[0].file(), "src/a.rs");
+        assert_eq!(hotspots[0].uncovered(), 3);
+        assert_eq!(hotspots[0].gain(), 3. / 7. * 100.);
+        assert_eq!(hotspots[1].file(), "src/b.rs");
+
+        let functions = lcov.function_hotspots();
+        let names: Vec<_> = functions.iter().map(|f| f.function().unwrap()).collect();
+        assert_eq!(names, ["test::bar", "test::foo"]);
+        assert_eq!(functions[0].uncovered(), 2);
+
+        let report = Report::hotspots(&lcov, 1);
+        assert!(report.metrics.is_empty());
+        assert_eq!(report.extra_columns, ["File", "Uncovered", "Gain"]);
+        let rows: Vec<(&str, &[Field])> = report
+            .rows()
+            .map(|row| (row.name.as_str(), row.extra.as_slice()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (
+                    "src/a.rs",
+                    &[
+                        "src/a.rs".into(),
+                        Field::Number(3),
+                        Field::Gain(3. / 7. * 100.)
+                    ][..]
+                ),
+                (
+                    "test::bar",
+                    &[
+                        "src/a.rs".into(),
+                        Field::Number(2),
+                        Field::Gain(2. / 7. * 100.)
+                    ][..]
+                ),
+            ]
+        );
+        assert_eq!(functions[1].uncovered(), 1);
     }
 }
@@ -1,7 +1,59 @@
+//! Parse and summarize LCOV tracefiles.
+//!
+//! [`Lcov`] holds the parsed files of a tracefile, [`LcovFile`] the coverage of a single source
+//! file and [`LcovSummary`] the totals. The coverage is counted per [`Metric`] in [`Metrics`].
+//! Parsing and computing never write to stdout; only the methods ending in `std` print a table.
+//!
+//! A [`Report`] holds the rows of a table, which a [`render::Renderer`] turns into terminal
+//! output, JSON, Markdown or CSV.
+//!
+//! ```
+//! use lcov_summary::{Lcov, Metric, Percentage};
+//!
+//! let tracefile = "SF:src/lib.rs\nDA:1,1\nDA:2,0\nLF:2\nLH:1\nend_of_record\n";
+//! let lcov = Lcov::from_reader(tracefile.as_bytes()).unwrap();
+//!
+//! let file = &lcov.files()[0];
+//! assert_eq!(file.name(), "src/lib.rs");
+//! assert_eq!(file.line_hits()[&1], 1);
+//!
+//! let summary = lcov.summary();
+//! assert_eq!(summary.metrics().lines.hit, 1);
+//! assert_eq!(summary.percentage(Metric::Lines), Percentage::Value(50.));
+//! assert_eq!(summary.percentage(Metric::Functions), Percentage::NotApplicable);
+//! ```
+#![warn(missing_docs)]
+
 
 mod parsers;
+mod badge;
+mod changes;
+mod compare;
+mod exclusions;
+mod history;
+mod hotspot;
+mod input;
 mod lcov_file;
+mod metric;
+mod per_test;
+mod ratchet;
+mod records;
+pub mod render;
+mod report;
+#[cfg(feature = "tui")]
+pub mod tui;
 
+pub use badge::{Badge, BadgeColor};
+pub use changes::ChangedLines;
+pub use compare::FileComparison;
+pub use exclusions::{ExcludedLines, ExclusionMarkers};
+pub use history::{History, HistoryEntry};
+pub use hotspot::Hotspot;
 pub use lcov_file::Lcov;
 pub use lcov_file::LcovFile;
 pub use lcov_file::LcovSummary;
+pub use metric::{Counter, Metric, Metrics, Percentage};
+pub use per_test::{TestCoverage, TestSummary};
+pub use ratchet::{Minimums, Ratchet, Violation};
+pub use records::Records;
+pub use report::{Change, Field, Group, Level, Report, Row, RowKind, Thresholds};
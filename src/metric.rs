@@ -0,0 +1,221 @@
+/// A coverage metric that can be reported for a source file.
+///
+/// Region coverage, as reported by some LLVM tools, is not supported yet. The enum is
+/// non-exhaustive, so more metrics can be added without breaking matches on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Metric {
+    /// Line coverage, from the `DA:`, `LF:` and `LH:` records.
+    Lines,
+    /// Function coverage, from the `FN:`, `FNDA:`, `FNF:` and `FNH:` records.
+    Functions,
+    /// Branch coverage, from the `BRDA:`, `BRF:` and `BRH:` records.
+    Branches,
+}
+
+impl Metric {
+    /// All metrics, in the order they are displayed.
+    pub const ALL: [Metric; 3] = [Metric::Lines, Metric::Functions, Metric::Branches];
+
+    /// Return the lowercase identifier of the metric, as used in machine readable output.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Metric::Lines => "lines",
+            Metric::Functions => "functions",
+            Metric::Branches => "branches",
+        }
+    }
+
+    /// Return the display name of the metric.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Lines => "Lines",
+            Metric::Functions => "Functions",
+            Metric::Branches => "Branches",
+        }
+    }
+}
+
+/// The hit and found counts of a single metric.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counter {
+    /// The number of items that were hit at least once.
+    pub hit: usize,
+    /// The number of instrumented items.
+    pub found: usize,
+}
+
+impl Counter {
+    /// Return the percentage of hits, which is not applicable when nothing was found.
+    pub fn percentage(&self) -> Percentage {
+        if self.found == 0 {
+            Percentage::NotApplicable
+        } else {
+            Percentage::Value(self.hit as f64 / self.found as f64 * 100.)
+        }
+    }
+}
+
+impl std::ops::AddAssign for Counter {
+    fn add_assign(&mut self, rhs: Self) {
+        self.hit += rhs.hit;
+        self.found += rhs.found;
+    }
+}
+
+/// A coverage percentage.
+///
+/// Files without anything to cover for a metric, like a module with only constants for the
+/// functions metric, have no meaningful percentage. Those are [`Percentage::NotApplicable`],
+/// which is displayed as `-` and sorts before every value.
+///
+/// Values are compared with [`f64::total_cmp`], so percentages have a total order: `-0.0` is
+/// below `0.0`, and a NaN equals itself.
+#[derive(Debug, Clone, Copy)]
+pub enum Percentage {
+    /// Nothing was found, so there is no percentage.
+    NotApplicable,
+    /// The percentage, between 0 and 100.
+    Value(f64),
+}
+
+impl Percentage {
+    /// Return the value of the percentage, if it is applicable.
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            Percentage::NotApplicable => None,
+            Percentage::Value(value) => Some(*value),
+        }
+    }
+
+    /// Return the difference in percentage points to another percentage, if both are applicable.
+    pub fn delta(&self, to: Percentage) -> Option<f64> {
+        Some(to.value()? - self.value()?)
+    }
+
+    /// Return `true` when the percentage is below a threshold.
+    ///
+    /// A percentage that is not applicable is never below a threshold.
+    pub fn is_below(&self, threshold: f64) -> bool {
+        self.value().is_some_and(|value| value < threshold)
+    }
+}
+
+impl PartialEq for Percentage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Percentage {}
+
+impl PartialOrd for Percentage {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Percentage {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Percentage::Value(a), Percentage::Value(b)) => a.total_cmp(b),
+            (a, b) => a.value().is_some().cmp(&b.value().is_some()),
+        }
+    }
+}
+
+impl std::fmt::Display for Percentage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Percentage::NotApplicable => "-".to_string(),
+            Percentage::Value(value) => {
+                let precision = f.precision().unwrap_or(2);
+                format!("{value:.precision$}%")
+            }
+        };
+        let width = f.width().unwrap_or(0);
+        write!(f, "{s:>width$}")
+    }
+}
+
+/// The counters of all metrics.
+///
+/// The struct is non-exhaustive, so it is created with [`Metrics::new`] or [`Default`] outside of
+/// this crate, and a counter for another metric can be added later.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The line counter.
+    pub lines: Counter,
+    /// The function counter.
+    pub functions: Counter,
+    /// The branch counter.
+    pub branches: Counter,
+}
+
+impl Metrics {
+    /// Create the counters of lines, functions and branches.
+    pub fn new(lines: Counter, functions: Counter, branches: Counter) -> Self {
+        Self {
+            lines,
+            functions,
+            branches,
+        }
+    }
+
+    /// Return the counter of a metric.
+    pub fn get(&self, metric: Metric) -> Counter {
+        match metric {
+            Metric::Lines => self.lines,
+            Metric::Functions => self.functions,
+            Metric::Branches => self.branches,
+        }
+    }
+
+    /// Return a mutable reference to the counter of a metric.
+    pub fn get_mut(&mut self, metric: Metric) -> &mut Counter {
+        match metric {
+            Metric::Lines => &mut self.lines,
+            Metric::Functions => &mut self.functions,
+            Metric::Branches => &mut self.branches,
+        }
+    }
+
+    /// Return `true` when the input reported anything for a metric.
+    pub fn has(&self, metric: Metric) -> bool {
+        self.get(metric).found > 0
+    }
+}
+
+impl std::ops::AddAssign for Metrics {
+    fn add_assign(&mut self, rhs: Self) {
+        for metric in Metric::ALL {
+            *self.get_mut(metric) += rhs.get(metric);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentage() {
+        let empty = Counter { hit: 0, found: 0 };
+        let half = Counter { hit: 1, found: 2 };
+
+        assert_eq!(empty.percentage(), Percentage::NotApplicable);
+        assert_eq!(empty.percentage().to_string(), "-");
+        assert_eq!(half.percentage().to_string(), "50.00%");
+        assert_eq!(format!("{:.1}", half.percentage()), "50.0%");
+
+        assert!(empty.percentage() < Percentage::Value(0.));
+        assert!(!empty.percentage().is_below(80.));
+        assert!(half.percentage().is_below(80.));
+        assert_eq!(empty.percentage().delta(half.percentage()), None);
+
+        assert_eq!(Percentage::Value(f64::NAN), Percentage::Value(f64::NAN));
+        assert_ne!(Percentage::Value(-0.), Percentage::Value(0.));
+        assert!(Percentage::Value(-0.) < Percentage::Value(0.));
+    }
+}
@@ -1,17 +1,40 @@
 use nom::bytes::complete::{tag, take_until1};
+use nom::character::complete::digit1;
+use nom::combinator::{all_consuming, map_res};
+use nom::error::{Error, ErrorKind};
 use nom::IResult;
 
+/// Parse a field that is a whole number.
+fn number(field: &str) -> Result<usize, nom::Err<Error<&str>>> {
+    let (_, number) = all_consuming(map_res(digit1, str::parse::<usize>))(field)?;
+    Ok(number)
+}
+
+pub fn test_name(input: &str) -> IResult<&str, &str> {
+    let (name, _) = tag("TN:")(input)?;
+    Ok(("", name))
+}
+
 pub fn source_file_path(input: &str) -> IResult<&str, &str> {
     let (file_path, _) = tag("SF:")(input)?;
     Ok(("", file_path))
 }
 
-pub fn function_name(input: &str) -> IResult<&str, (usize, &str)> {
+/// Parse `FN:<line>,<name>`, or `FN:<line>,<end line>,<name>` as written by LCOV 2.x.
+pub fn function_name(input: &str) -> IResult<&str, (usize, Option<usize>, &str)> {
     let (input, _) = tag("FN:")(input)?;
     let (input, line_number) = take_until1(",")(input)?;
     let (name, _) = tag(",")(input)?;
 
-    Ok(("", (line_number.parse::<usize>().unwrap(), name)))
+    let (end_line, name) = match name.split_once(',') {
+        Some((end_line, rest)) if !rest.is_empty() => match end_line.parse::<usize>() {
+            Ok(end_line) => (Some(end_line), rest),
+            Err(_) => (None, name),
+        },
+        _ => (None, name),
+    };
+
+    Ok(("", (number(line_number)?, end_line, name)))
 }
 
 pub fn function_hit_count(input: &str) -> IResult<&str, (usize, &str)> {
@@ -19,12 +42,44 @@ pub fn function_hit_count(input: &str) -> IResult<&str, (usize, &str)> {
     let (input, line_number) = take_until1(",")(input)?;
     let (name, _) = tag(",")(input)?;
 
-    Ok(("", (line_number.parse::<usize>().unwrap(), name)))
+    Ok(("", (number(line_number)?, name)))
+}
+
+/// Parse a `FNL:<index>,<line>[,<end line>]` record of LCOV 2.x, with the location of the
+/// functions that `FNA:` records refer to by index.
+pub fn function_location(input: &str) -> IResult<&str, (usize, usize, Option<usize>)> {
+    let (input, _) = tag("FNL:")(input)?;
+    let (input, index) = take_until1(",")(input)?;
+    let (input, _) = tag(",")(input)?;
+    let (line_number, end_line) = match input.split_once(',') {
+        Some((line_number, end_line)) => (line_number, Some(number(end_line)?)),
+        None => (input, None),
+    };
+
+    Ok(("", (number(index)?, number(line_number)?, end_line)))
+}
+
+/// Parse a `FNA:<index>,<hits>,<name>` record of LCOV 2.x, with the hit count of a function at
+/// the location of the `FNL:` record with the same index.
+pub fn function_alias(input: &str) -> IResult<&str, (usize, usize, &str)> {
+    let (input, _) = tag("FNA:")(input)?;
+    let (input, index) = take_until1(",")(input)?;
+    let (input, _) = tag(",")(input)?;
+    let (input, hits) = take_until1(",")(input)?;
+    let (name, _) = tag(",")(input)?;
+
+    Ok(("", (number(index)?, number(hits)?, name)))
+}
+
+/// Parse a `VER:<version>` record of LCOV 2.x, with the version or checksum of the source file.
+pub fn source_version(input: &str) -> IResult<&str, &str> {
+    let (version, _) = tag("VER:")(input)?;
+    Ok(("", version))
 }
 
 fn tag_number<'i>(input: &'i str, t: &'_ str) -> IResult<&'i str, usize> {
     let (found, _) = tag(t)(input)?;
-    Ok(("", found.parse::<usize>().unwrap()))
+    Ok(("", number(found)?))
 }
 
 pub fn functions_found(input: &str) -> IResult<&str, usize> {
@@ -35,19 +90,15 @@ pub fn functions_hit(input: &str) -> IResult<&str, usize> {
     tag_number(input, "FNH:")
 }
 
-//pub fn line_number_hit_count(input: &str) -> IResult<&str, (usize, usize)> {
-//let (input, _) = tag("DA:")(input)?;
-//let (input, line_number) = take_until1(",")(input)?;
-//let (hit_count, _) = tag(",")(input)?;
+pub fn line_number_hit_count(input: &str) -> IResult<&str, (usize, usize)> {
+    let (input, _) = tag("DA:")(input)?;
+    let (input, line_number) = take_until1(",")(input)?;
+    let (input, _) = tag(",")(input)?;
+    // An optional checksum can follow the hit count.
+    let hit_count = input.split(',').next().unwrap_or_default();
 
-//Ok((
-//"",
-//(
-//line_number.parse::<usize>().unwrap(),
-//hit_count.parse::<usize>().unwrap(),
-//),
-//))
-//}
+    Ok(("", (number(line_number)?, number(hit_count)?)))
+}
 
 pub fn lines_found(input: &str) -> IResult<&str, usize> {
     tag_number(input, "LF:")
@@ -57,6 +108,32 @@ pub fn lines_hit(input: &str) -> IResult<&str, usize> {
     tag_number(input, "LH:")
 }
 
+/// Parse a `BRDA:<line>,<block>,<branch>,<taken>` record, where a `taken` of `-` means that the
+/// block containing the branch was never executed.
+///
+/// The block and branch are returned as they are written. LCOV 2.x marks the block of an
+/// exception branch with an `e` prefix, and names the branches of an expression with text that
+/// can contain commas, so the hit count is the last field.
+pub fn branch_data(input: &str) -> IResult<&str, (usize, &str, &str, Option<usize>)> {
+    let (input, _) = tag("BRDA:")(input)?;
+    let (input, line_number) = take_until1(",")(input)?;
+    let (input, _) = tag(",")(input)?;
+    let (input, block) = take_until1(",")(input)?;
+    let (input, _) = tag(",")(input)?;
+    let Some((branch, taken)) = input
+        .rsplit_once(',')
+        .filter(|(branch, _)| !branch.is_empty())
+    else {
+        return Err(nom::Err::Error(Error::new(input, ErrorKind::TakeUntil)));
+    };
+
+    let taken = match taken {
+        "-" => None,
+        taken => Some(number(taken)?),
+    };
+    Ok(("", (number(line_number)?, block, branch, taken)))
+}
+
 pub fn branches_found(input: &str) -> IResult<&str, usize> {
     tag_number(input, "BRF:")
 }
@@ -69,6 +146,13 @@ pub fn branches_hit(input: &str) -> IResult<&str, usize> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_test_name() {
+        let input = "TN:integration";
+        let (_, name) = test_name(input).unwrap();
+        assert_eq!(name, "integration");
+    }
+
     #[test]
     fn test_source_file_path() {
         let input = "SF:/home/thvdveld/source/vub/smoltcp/src/iface/fragmentation.rs";
@@ -85,13 +169,17 @@ mod tests {
     fn test_function_name() {
         let input = "FN:110,_RINvMs2_NtNtCshpVWEOJQZRA_7smoltcp5iface13fragmentationINtB6_15PacketAssemblerpE8add_withpEBa_";
 
-        let (_, (line_number, name)) = function_name(input).unwrap();
+        let (_, (line_number, end_line, name)) = function_name(input).unwrap();
 
         assert_eq!(line_number, 110);
+        assert_eq!(end_line, None);
         assert_eq!(
             name,
             "_RINvMs2_NtNtCshpVWEOJQZRA_7smoltcp5iface13fragmentationINtB6_15PacketAssemblerpE8add_withpEBa_"
         );
+
+        let (_, (line_number, end_line, name)) = function_name("FN:3,12,main").unwrap();
+        assert_eq!((line_number, end_line, name), (3, Some(12), "main"));
     }
 
     #[test]
@@ -107,6 +195,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_function_location() {
+        let (_, location) = function_location("FNL:0,3,12").unwrap();
+        assert_eq!(location, (0, 3, Some(12)));
+
+        let (_, location) = function_location("FNL:1,20").unwrap();
+        assert_eq!(location, (1, 20, None));
+    }
+
+    #[test]
+    fn test_function_alias() {
+        let (_, alias) = function_alias("FNA:0,5,_RNvCs123_4test3foo").unwrap();
+        assert_eq!(alias, (0, 5, "_RNvCs123_4test3foo"));
+    }
+
+    #[test]
+    fn test_source_version() {
+        let (_, version) = source_version("VER:#1a2b3c").unwrap();
+        assert_eq!(version, "#1a2b3c");
+    }
+
     #[test]
     fn test_functions_found() {
         let input = "FNF:38";
@@ -129,6 +238,48 @@ mod tests {
         assert_eq!(hit, 0);
     }
 
+    #[test]
+    fn test_line_number_hit_count_with_checksum() {
+        let input = "DA:17,3,mEQhX7N5U9Hsbsx/sMDrBw";
+        let (_, (line, hit)) = line_number_hit_count(input).unwrap();
+        assert_eq!(line, 17);
+        assert_eq!(hit, 3);
+    }
+
+    #[test]
+    fn test_branch_data() {
+        let input = "BRDA:45,0,1,12";
+        let (_, (line, block, branch, taken)) = branch_data(input).unwrap();
+        assert_eq!((line, block, branch, taken), (45, "0", "1", Some(12)));
+
+        let input = "BRDA:45,0,2,-";
+        let (_, (_, _, _, taken)) = branch_data(input).unwrap();
+        assert_eq!(taken, None);
+    }
+
+    #[test]
+    fn test_branch_data_exception() {
+        let (_, branch) = branch_data("BRDA:10,e0,1,3").unwrap();
+        assert_eq!(branch, (10, "e0", "1", Some(3)));
+    }
+
+    #[test]
+    fn test_branch_data_expression() {
+        let (_, branch) = branch_data("BRDA:7,0,(a, b) == 'true',-").unwrap();
+        assert_eq!(branch, (7, "0", "(a, b) == 'true'", None));
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        assert!(branch_data("BRDA:x,0,1,3").is_err());
+        assert!(branch_data("BRDA:10,0,1,many").is_err());
+        assert!(branch_data("BRDA:10,0,1").is_err());
+        assert!(function_location("FNL:0,3,end").is_err());
+        assert!(function_alias("FNA:zero,5,foo").is_err());
+        assert!(line_number_hit_count("DA:1,-1").is_err());
+        assert!(lines_found("LF:").is_err());
+    }
+
     #[test]
     fn test_branches_found() {
         let input = "BRF:0";
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use crate::metric::{Metric, Metrics};
+
+/// The coverage that the records of a single test name contributed to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCoverage {
+    pub(crate) name: String,
+    pub(crate) metrics: Metrics,
+    /// The hit counts of the lines that were covered by this test.
+    pub(crate) line_hits: BTreeMap<usize, usize>,
+}
+
+impl TestCoverage {
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            name,
+            metrics: Metrics::default(),
+            line_hits: Default::default(),
+        }
+    }
+
+    /// Merge the coverage of another record with the same test name into this one.
+    pub(crate) fn merge(&mut self, other: TestCoverage) {
+        for (line, hits) in other.line_hits {
+            *self.line_hits.entry(line).or_default() += hits;
+        }
+
+        for metric in Metric::ALL {
+            let counter = self.metrics.get_mut(metric);
+            let other = other.metrics.get(metric);
+            counter.found = counter.found.max(other.found);
+            counter.hit = counter.hit.max(other.hit);
+        }
+
+        if !self.line_hits.is_empty() {
+            self.metrics.lines.hit = self.line_hits.len();
+        }
+    }
+
+    /// Return the name of the test.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the counters of all metrics for this test.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Return the hit counts of the lines that were covered by this test.
+    pub fn line_hits(&self) -> &BTreeMap<usize, usize> {
+        &self.line_hits
+    }
+}
+
+/// The coverage of a single test name over all files of an LCOV file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestSummary {
+    pub(crate) name: String,
+    pub(crate) metrics: Metrics,
+    pub(crate) unique_lines: usize,
+}
+
+impl TestSummary {
+    /// Return the name of the test.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the counters of all metrics for this test.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Return the number of lines that are covered by this test only.
+    pub fn unique_lines(&self) -> usize {
+        self.unique_lines
+    }
+}
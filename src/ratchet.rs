@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::metric::{Metric, Metrics, Percentage};
+use crate::render::relative_path;
+use crate::Lcov;
+
+/// The minimum coverage per metric, in percent.
+pub type Minimums = BTreeMap<Metric, f64>;
+
+/// The minimum coverage of the totals, and optionally of every source file, that may not drop.
+///
+/// The minimums are only raised by [`Ratchet::update`], so coverage can only go up over time.
+/// Source files are keyed by their path relative to a root, like the root of the repository, so
+/// the ratchet file can be checked on other machines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ratchet {
+    /// The minimum coverage of the totals.
+    pub totals: Minimums,
+    /// The minimum coverage of every source file, when the ratchet is per file.
+    pub files: BTreeMap<String, Minimums>,
+}
+
+/// A coverage that dropped below its minimum in a [`Ratchet`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The path of the source file, or `None` for the totals.
+    pub file: Option<String>,
+    /// The metric that dropped.
+    pub metric: Metric,
+    /// The minimum coverage, in percent.
+    pub minimum: f64,
+    /// The current coverage.
+    pub actual: Percentage,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {:.2} < {:.2}%",
+            self.file.as_deref().unwrap_or("total"),
+            self.metric.key(),
+            self.actual,
+            self.minimum
+        )
+    }
+}
+
+impl Ratchet {
+    /// Parse a ratchet file. A file that does not exist is an empty ratchet.
+    pub fn parse(path: &Path) -> Result<Self> {
+        let value: Value = match File::open(path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut files = BTreeMap::new();
+        if let Some(object) = value["files"].as_object() {
+            for (name, minimums) in object {
+                files.insert(name.clone(), minimums_from_json(minimums)?);
+            }
+        }
+
+        Ok(Self {
+            totals: minimums_from_json(&value["totals"])?,
+            files,
+        })
+    }
+
+    /// Write the ratchet to a file, as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let files: serde_json::Map<String, Value> = self
+            .files
+            .iter()
+            .map(|(name, minimums)| (name.clone(), minimums_to_json(minimums)))
+            .collect();
+        let value = json!({
+            "totals": minimums_to_json(&self.totals),
+            "files": files,
+        });
+        std::fs::write(path, format!("{value:#}\n"))?;
+        Ok(())
+    }
+
+    /// Return the coverage that dropped below its minimum.
+    ///
+    /// Coverage that is not applicable, and source files that are not in the LCOV file, are not
+    /// checked.
+    pub fn check(&self, lcov: &Lcov, root: Option<&Path>) -> Vec<Violation> {
+        let mut violations = vec![];
+        check(None, &self.totals, &lcov.summary().metrics, &mut violations);
+
+        for file in lcov.files() {
+            let name = relative_path(file.name(), root);
+            if let Some(minimums) = self.files.get(name) {
+                check(Some(name), minimums, file.metrics(), &mut violations);
+            }
+        }
+
+        violations
+    }
+
+    /// Raise the minimums to the current coverage, where it improved.
+    ///
+    /// With `per_file`, or when the ratchet already has source files, every source file of the
+    /// LCOV file is added, and source files that no longer exist are removed.
+    pub fn update(&mut self, lcov: &Lcov, root: Option<&Path>, per_file: bool) {
+        raise(&mut self.totals, &lcov.summary().metrics);
+
+        if per_file || !self.files.is_empty() {
+            let mut files = BTreeMap::new();
+            for file in lcov.files() {
+                let name = relative_path(file.name(), root);
+                let mut minimums = self.files.remove(name).unwrap_or_default();
+                raise(&mut minimums, file.metrics());
+                files.insert(name.to_string(), minimums);
+            }
+            self.files = files;
+        }
+    }
+}
+
+fn check(
+    file: Option<&str>,
+    minimums: &Minimums,
+    metrics: &Metrics,
+    violations: &mut Vec<Violation>,
+) {
+    for (&metric, &minimum) in minimums {
+        let actual = metrics.get(metric).percentage();
+        if actual.is_below(minimum) {
+            violations.push(Violation {
+                file: file.map(String::from),
+                metric,
+                minimum,
+                actual,
+            });
+        }
+    }
+}
+
+fn raise(minimums: &mut Minimums, metrics: &Metrics) {
+    for metric in Metric::ALL {
+        // Round down, so the same coverage never fails because of floating point errors.
+        let Some(value) = metrics.get(metric).percentage().value() else {
+            continue;
+        };
+        let value = (value * 100.).floor() / 100.;
+
+        let minimum = minimums.entry(metric).or_insert(value);
+        *minimum = minimum.max(value);
+    }
+}
+
+fn minimums_to_json(minimums: &Minimums) -> Value {
+    let mut value = json!({});
+    for (metric, minimum) in minimums {
+        value[metric.key()] = json!(minimum);
+    }
+    value
+}
+
+fn minimums_from_json(value: &Value) -> Result<Minimums> {
+    let mut minimums = Minimums::new();
+    for metric in Metric::ALL {
+        match &value[metric.key()] {
+            Value::Null => {}
+            minimum => {
+                let minimum = minimum.as_f64().ok_or_else(|| {
+                    anyhow!("invalid minimum for {} in ratchet file", metric.key())
+                })?;
+                minimums.insert(metric, minimum);
+            }
+        }
+    }
+    Ok(minimums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lcov(hits: [usize; 3]) -> Lcov {
+        let input = format!(
+            "SF:/repo/src/a.rs\nDA:1,{}\nDA:2,{}\nDA:3,{}\nLF:3\nLH:{}\nend_of_record\n",
+            hits[0],
+            hits[1],
+            hits[2],
+            hits.iter().filter(|&&hits| hits > 0).count()
+        );
+        Lcov::from_reader(input.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_ratchet() {
+        let root = Some(Path::new("/repo"));
+        let mut ratchet = Ratchet::default();
+        ratchet.update(&lcov([1, 0, 0]), root, true);
+        assert_eq!(ratchet.totals[&Metric::Lines], 33.33);
+        assert_eq!(ratchet.files["src/a.rs"][&Metric::Lines], 33.33);
+        assert!(!ratchet.totals.contains_key(&Metric::Functions));
+
+        ratchet.update(&lcov([1, 1, 0]), root, false);
+        assert_eq!(ratchet.totals[&Metric::Lines], 66.66);
+        assert!(ratchet.check(&lcov([1, 1, 0]), root).is_empty());
+
+        ratchet.update(&lcov([1, 0, 0]), root, false);
+        assert_eq!(ratchet.totals[&Metric::Lines], 66.66);
+
+        let violations = ratchet.check(&lcov([0, 0, 1]), root);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].to_string(), "total lines 33.33% < 66.66%");
+        assert_eq!(violations[1].to_string(), "src/a.rs lines 33.33% < 66.66%");
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use anyhow::{bail, Result};
+use nom::IResult;
+
+use crate::input;
+use crate::lcov_file::add_taken;
+use crate::parsers::*;
+use crate::per_test::TestCoverage;
+use crate::LcovFile;
+
+/// A streaming parser that yields one [`LcovFile`] for every record of an LCOV file.
+///
+/// A record ends at `end_of_record`, or when the next `SF:` line starts. Only the record that is
+/// being parsed is kept in memory.
+///
+/// Records are yielded as they appear in the input. When a source file has several records, for
+/// example one per test name, they are not merged.
+pub struct Records<'a> {
+    reader: Box<dyn BufRead + 'a>,
+    line: String,
+    tests: bool,
+    test_name: Option<String>,
+    current: Option<LcovFile>,
+    /// The start and end line of the functions of the current record, keyed by the index of
+    /// their `FNL:` record.
+    function_locations: HashMap<usize, (usize, Option<usize>)>,
+    /// The name of the first `FNA:` record of every `FNL:` index of the current record, which
+    /// the other aliases of the function are counted under.
+    function_names: HashMap<usize, String>,
+}
+
+impl<'a> Records<'a> {
+    /// Create a record iterator over a reader.
+    ///
+    /// Gzip and zstd compressed input is detected from the magic bytes and decompressed.
+    pub fn new(reader: impl BufRead + 'a) -> Result<Self> {
+        Ok(Self {
+            reader: input::decompress(reader)?,
+            line: String::new(),
+            tests: true,
+            test_name: None,
+            current: None,
+            function_locations: HashMap::new(),
+            function_names: HashMap::new(),
+        })
+    }
+
+    /// Leave the test names out of the records, so their hits are not copied into every test.
+    ///
+    /// This is enough when only the totals are needed.
+    pub fn without_tests(mut self) -> Self {
+        self.tests = false;
+        self
+    }
+
+    /// Replace the current record, and return the finished one.
+    fn finish(&mut self, next: Option<LcovFile>) -> Option<LcovFile> {
+        self.function_locations.clear();
+        self.function_names.clear();
+        let mut file = std::mem::replace(&mut self.current, next)?;
+        file.finish_record();
+        Some(file)
+    }
+
+    /// Parse a single line into the current record, and return the record when it is finished.
+    fn parse_line(&mut self, line: &str) -> Result<Option<LcovFile>> {
+        if line.starts_with("TN:") {
+            let name = parse(line, test_name)?;
+            self.test_name = (!name.is_empty()).then(|| name.to_string());
+            return Ok(None);
+        }
+
+        if line.starts_with("SF:") {
+            let source = parse(line, source_file_path)?;
+            let mut file = LcovFile::new(&source);
+            if self.tests {
+                file.tests
+                    .extend(self.test_name.clone().map(TestCoverage::new));
+            }
+            return Ok(self.finish(Some(file)));
+        }
+
+        if line == "end_of_record" {
+            return Ok(self.finish(None));
+        }
+
+        let Some(file) = self.current.as_mut() else {
+            return Ok(None);
+        };
+
+        // `FN:` and `FNDA:` records can come in any order, and some tools write an `FNDA:` record
+        // without an `FN:` record, or several `FNDA:` records for the same function.
+        if line.starts_with("FN:") {
+            let (line, end_line, name) = parse(line, function_name)?;
+            file.function_hits.entry(name.to_string()).or_insert(0);
+            file.function_lines.entry(name.to_string()).or_insert(line);
+            if let Some(end_line) = end_line {
+                file.function_end_lines
+                    .entry(name.to_string())
+                    .or_insert(end_line);
+            }
+        } else if line.starts_with("FNDA:") {
+            let (hits, name) = parse(line, function_hit_count)?;
+            *file.function_hits.entry(name.to_string()).or_default() += hits;
+        } else if line.starts_with("FNL:") {
+            let (index, line, end_line) = parse(line, function_location)?;
+            self.function_locations.insert(index, (line, end_line));
+        } else if line.starts_with("FNA:") {
+            // All aliases of an index are the same function, like the instances of a template,
+            // so they are counted once, with the hits of every alias.
+            let (index, hits, name) = parse(line, function_alias)?;
+            if let Some(function) = self.function_names.get(&index) {
+                *file.function_hits.entry(function.clone()).or_default() += hits;
+                if function != name {
+                    let aliases = file.function_aliases.entry(function.clone()).or_default();
+                    if !aliases.iter().any(|alias| alias == name) {
+                        aliases.push(name.to_string());
+                    }
+                }
+                return Ok(None);
+            }
+
+            self.function_names.insert(index, name.to_string());
+            *file.function_hits.entry(name.to_string()).or_default() += hits;
+            if let Some(&(line, end_line)) = self.function_locations.get(&index) {
+                file.function_lines.entry(name.to_string()).or_insert(line);
+                if let Some(end_line) = end_line {
+                    file.function_end_lines
+                        .entry(name.to_string())
+                        .or_insert(end_line);
+                }
+            }
+        } else if line.starts_with("VER:") {
+            let version = parse(line, source_version)?;
+            file.version = Some(version.to_string());
+        } else if line.starts_with("FNF:") {
+            let found = parse(line, functions_found)?;
+            file.metrics.functions.found = found;
+        } else if line.starts_with("FNH:") {
+            let hit = parse(line, functions_hit)?;
+            file.metrics.functions.hit = hit;
+        } else if line.starts_with("DA:") {
+            let (line_number, hits) = parse(line, line_number_hit_count)?;
+            *file.line_hits.entry(line_number).or_default() += hits;
+        } else if line.starts_with("LF:") {
+            let found = parse(line, lines_found)?;
+            file.metrics.lines.found = found;
+        } else if line.starts_with("LH:") {
+            let hit = parse(line, lines_hit)?;
+            file.metrics.lines.hit = hit;
+        } else if line.starts_with("BRDA:") {
+            let (line_number, block, branch, taken) = parse(line, branch_data)?;
+            let hits = file
+                .branch_hits
+                .entry((line_number, block.to_string(), branch.to_string()))
+                .or_default();
+            *hits = add_taken(*hits, taken);
+        } else if line.starts_with("BRF:") {
+            let found = parse(line, branches_found)?;
+            file.metrics.branches.found = found;
+        } else if line.starts_with("BRH:") {
+            let hit = parse(line, branches_hit)?;
+            file.metrics.branches.hit = hit;
+        }
+
+        Ok(None)
+    }
+}
+
+/// Run a record parser on a line, and turn a parse error into an error that names the line.
+fn parse<'a, T>(line: &'a str, parser: impl Fn(&'a str) -> IResult<&'a str, T>) -> Result<T> {
+    match parser(line) {
+        Ok((_, value)) => Ok(value),
+        Err(_) => bail!("invalid record `{line}`"),
+    }
+}
+
+impl Iterator for Records<'_> {
+    type Item = Result<LcovFile>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return self.finish(None).map(Ok),
+                Ok(_) => (),
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let line = std::mem::take(&mut self.line);
+            let record = self.parse_line(line.trim_end_matches(['\n', '\r']));
+            self.line = line;
+
+            match record {
+                Ok(Some(record)) => return Some(Ok(record)),
+                Ok(None) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// The minimum size of a chunk of records that is parsed on a single thread.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Parse the records of a reader on the rayon thread pool.
+///
+/// The input is split into chunks of whole records at `end_of_record` lines. A chunk starts with
+/// the last `TN:` line before it, so the records of a chunk keep their test name. Every chunk is
+/// parsed with `parse`, and the results are passed to `merge` in the order of the input. Only a
+/// bounded number of chunks is kept in memory at once.
+pub(crate) fn parse_parallel<'a, T: Send>(
+    reader: impl BufRead + 'a,
+    parse: impl Fn(&str) -> Result<T> + Sync,
+    mut merge: impl FnMut(T),
+) -> Result<()> {
+    use rayon::prelude::*;
+
+    let mut reader = input::decompress(reader)?;
+    let window = rayon::current_num_threads() * 4;
+    let mut chunks = Vec::with_capacity(window);
+
+    let mut test_name = String::new();
+    let mut done = false;
+    while !done {
+        let mut chunk = String::with_capacity(CHUNK_SIZE);
+        chunk.push_str(&test_name);
+        loop {
+            let start = chunk.len();
+            if reader.read_line(&mut chunk)? == 0 {
+                done = true;
+                break;
+            }
+            let line = &chunk[start..];
+            if line.starts_with("TN:") {
+                test_name = line.trim_end().to_string() + "\n";
+            }
+            if chunk.len() >= CHUNK_SIZE && line.trim_end() == "end_of_record" {
+                break;
+            }
+        }
+
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+
+        if chunks.len() == window || done {
+            let results: Vec<_> = chunks.par_iter().map(|chunk| parse(chunk)).collect();
+            for result in results {
+                merge(result?);
+            }
+            chunks.clear();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Counter;
+
+    #[test]
+    fn test_records() {
+        let input = "TN:\nSF:src/a.rs\nLF:4\nLH:3\nend_of_record\nSF:src/b.rs\r\nLF:2\r\nLH:1\r\n";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].metrics().lines.hit, 3);
+        assert_eq!(files[1].metrics().lines.found, 2);
+    }
+
+    #[test]
+    fn test_records_test_name() {
+        let input =
+            "TN:unit\nSF:src/a.rs\nend_of_record\nSF:src/b.rs\nend_of_record\nTN:\nSF:src/c.rs\n";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(files[0].tests[0].name, "unit");
+        assert_eq!(files[1].tests[0].name, "unit");
+        assert!(files[2].tests.is_empty());
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .without_tests()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(files.iter().all(|file| file.tests.is_empty()));
+    }
+
+    #[test]
+    fn test_records_invalid() {
+        let input = "SF:src/a.rs\nDA:1,1\nBRDA:10,e0,1,3\nBRDA:oops\nend_of_record\n";
+
+        let err = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "invalid record `BRDA:oops`");
+        assert!(crate::Lcov::from_reader(input.as_bytes()).is_err());
+
+        let input = input.replace("BRDA:oops\n", "");
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            files[0].branch_hits()[&(10, "e0".into(), "1".into())],
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_records_without_summary() {
+        let input = "SF:src/a.rs\nFN:1,foo\nFNDA:1,foo\nDA:1,1\nDA:2,0\nend_of_record\n";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(files[0].metrics().lines, Counter { hit: 1, found: 2 });
+        assert_eq!(files[0].metrics().functions, Counter { hit: 1, found: 1 });
+
+        let twice = crate::Lcov::from_reader(input.repeat(2).as_bytes()).unwrap();
+        assert_eq!(twice.files()[0].metrics(), files[0].metrics());
+    }
+
+    #[test]
+    fn test_records_without_end_of_record() {
+        let input = "SF:src/a.rs\nLF:4\nSF:src/b.rs\nLF:2\n";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].metrics().lines.found, 4);
+        assert_eq!(files[1].metrics().lines.found, 2);
+    }
+
+    #[test]
+    fn test_records_function_order() {
+        let input = "\
+SF:src/a.rs
+FNDA:2,foo
+FN:3,9,foo
+FNDA:1,foo
+FNDA:4,bar
+FN:12,baz
+FN:14,baz
+BRDA:5,0,0,-
+BRDA:5,0,0,2
+BRDA:5,0,1,1
+BRDA:5,0,1,3
+BRDA:5,0,2,-
+BRDA:5,0,2,-
+end_of_record
+";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let file = &files[0];
+        assert_eq!(file.function_hits()["foo"], 3);
+        assert_eq!(file.function_hits()["bar"], 4);
+        assert_eq!(file.function_hits()["baz"], 0);
+        assert_eq!(file.function_lines()["foo"], 3);
+        assert_eq!(file.function_lines()["baz"], 12);
+        assert!(!file.function_lines().contains_key("bar"));
+        assert_eq!(file.function_end_lines()["foo"], 9);
+        assert!(!file.function_end_lines().contains_key("baz"));
+        let branch = |branch: &str| file.branch_hits()[&(5, "0".into(), branch.into())];
+        assert_eq!(branch("0"), Some(2));
+        assert_eq!(branch("1"), Some(4));
+        assert_eq!(branch("2"), None);
+    }
+
+    #[test]
+    fn test_records_function_aliases() {
+        let input = "\
+SF:src/a.rs
+FNL:0,1,5
+FNA:0,2,foo<int>
+FNA:0,3,foo<long>
+FNF:1
+FNH:1
+end_of_record
+SF:src/a.rs
+FNL:0,1,5
+FNA:0,1,foo<long>
+FNA:0,0,foo<int>
+FNF:1
+FNH:1
+end_of_record
+";
+
+        let files = Records::new(input.as_bytes())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(files[0].function_hits().len(), 1);
+        assert_eq!(files[0].function_hits()["foo<int>"], 5);
+        assert_eq!(files[0].function_aliases()["foo<int>"], ["foo<long>"]);
+        assert_eq!(files[0].function_lines()["foo<int>"], 1);
+        assert_eq!(files[1].function_aliases()["foo<long>"], ["foo<int>"]);
+
+        let mut lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+        let file = &mut lcov.files_mut()[0];
+        assert_eq!(file.function_hits().len(), 1);
+        assert_eq!(file.function_hits()["foo<int>"], 6);
+        assert_eq!(file.function_aliases()["foo<int>"], ["foo<long>"]);
+        assert_eq!(file.metrics().functions, Counter { hit: 1, found: 1 });
+
+        file.exclude_functions(|name| name == "foo<long>");
+        assert!(file.function_hits().is_empty());
+        assert!(file.function_aliases().is_empty());
+    }
+
+    #[test]
+    fn test_parse_parallel_keeps_test_name() {
+        let mut input = "TN:unit\n".to_string();
+        while input.len() <= 2 * CHUNK_SIZE {
+            let i = input.len();
+            input.push_str(&format!("SF:src/{i}.rs\nLF:1\nLH:1\nend_of_record\n"));
+        }
+
+        let mut files = vec![];
+        parse_parallel(
+            input.as_bytes(),
+            |chunk| Records::new(chunk.as_bytes())?.collect::<Result<Vec<_>>>(),
+            |chunk| files.extend(chunk),
+        )
+        .unwrap();
+
+        assert!(files.len() > 40_000);
+        assert!(files.iter().all(|file| file.tests()[0].name() == "unit"));
+    }
+
+    #[test]
+    fn test_parse_parallel_keeps_order() {
+        let mut input = String::new();
+        for i in 0..100_000 {
+            input.push_str(&format!("SF:src/{i}.rs\nLF:{i}\nLH:0\nend_of_record\n"));
+        }
+
+        let mut files = vec![];
+        parse_parallel(
+            input.as_bytes(),
+            |chunk| Records::new(chunk.as_bytes())?.collect::<Result<Vec<_>>>(),
+            |chunk| files.extend(chunk),
+        )
+        .unwrap();
+
+        assert_eq!(files.len(), 100_000);
+        for (i, file) in files.iter().enumerate() {
+            assert_eq!(file.metrics().lines.found, i);
+        }
+    }
+}
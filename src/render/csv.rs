@@ -0,0 +1,177 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::Renderer;
+use crate::metric::{Counter, Metric};
+use crate::report::{Field, Report, Row, RowKind};
+
+/// Render a report as delimiter separated values, with a header line.
+///
+/// Every row that is not a delta becomes a record. The columns of lines, functions and branches
+/// are always present, so the header does not depend on the input, except for reports without
+/// metrics like [`Report::uncovered_functions`]. A delta row adds its
+/// differences to the `*_delta` columns of the record before it, which are only present when the
+/// report has delta rows.
+///
+/// Source files are named by their full path, so files with the same name in different crates of
+/// a workspace stay apart. Percentages and gains are written without a `%` sign, and left empty
+/// when not applicable.
+#[derive(Debug, Clone)]
+pub struct Csv {
+    /// The character that separates the fields.
+    pub delimiter: char,
+    /// The directory that paths of source files are made relative to, when they are inside it.
+    pub root: Option<PathBuf>,
+}
+
+impl Csv {
+    /// Create a renderer for tab separated values.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Csv {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            root: None,
+        }
+    }
+}
+
+impl Renderer for Csv {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let metrics = Self::columns(report);
+        let deltas = report
+            .rows()
+            .any(|row| matches!(row.kind, RowKind::Delta { .. }));
+
+        let mut header = vec!["name".to_string(), "kind".to_string()];
+        for metric in &metrics {
+            for column in ["hit", "total", "percentage"] {
+                header.push(format!("{}_{column}", metric.key()));
+            }
+        }
+        if deltas {
+            for metric in &metrics {
+                for column in ["hit", "total", "percentage"] {
+                    header.push(format!("{}_{column}_delta", metric.key()));
+                }
+            }
+        }
+        for column in &report.extra_columns {
+            header.push(column.to_lowercase().replace(' ', "_"));
+        }
+        self.write_record(out, &header)?;
+
+        for group in &report.groups {
+            let mut rows = group.rows.iter().peekable();
+            while let Some(row) = rows.next() {
+                let kind = match row.kind {
+                    RowKind::Item => "item",
+                    RowKind::Total => "total",
+                    RowKind::Delta { .. } => continue,
+                };
+
+                let mut fields = vec![self.name(row), kind.to_string()];
+                for &metric in &metrics {
+                    fields.extend(Self::counter_fields(row.metrics.get(metric)));
+                }
+
+                if deltas {
+                    let delta = rows.next_if(|row| matches!(row.kind, RowKind::Delta { .. }));
+                    for &metric in &metrics {
+                        match delta {
+                            Some(delta) => {
+                                let RowKind::Delta { from } = &delta.kind else {
+                                    unreachable!()
+                                };
+                                fields.extend(Self::delta_fields(
+                                    from.get(metric),
+                                    delta.metrics.get(metric),
+                                ));
+                            }
+                            None => fields.extend([String::new(), String::new(), String::new()]),
+                        }
+                    }
+                }
+
+                fields.extend(row.extra.iter().map(Self::extra_field));
+                self.write_record(out, &fields)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Csv {
+    /// Return the metrics that get columns, which are all metrics unless the report has no
+    /// metrics.
+    fn columns(report: &Report) -> Vec<Metric> {
+        if report.metrics.is_empty() {
+            return vec![];
+        }
+        Metric::ALL.to_vec()
+    }
+
+    /// Return the name of a row, which is the path of its source file if it has one.
+    fn name(&self, row: &Row) -> String {
+        let Some(path) = &row.path else {
+            return row.name.clone();
+        };
+        let path = Path::new(path);
+        self.root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn counter_fields(counter: Counter) -> [String; 3] {
+        [
+            counter.hit.to_string(),
+            counter.found.to_string(),
+            Self::percentage_field(counter.percentage().value()),
+        ]
+    }
+
+    fn delta_fields(from: Counter, to: Counter) -> [String; 3] {
+        [
+            (to.hit as i64 - from.hit as i64).to_string(),
+            (to.found as i64 - from.found as i64).to_string(),
+            Self::percentage_field(from.percentage().delta(to.percentage())),
+        ]
+    }
+
+    fn extra_field(field: &Field) -> String {
+        match field {
+            Field::Gain(gain) => Self::percentage_field(Some(*gain)),
+            field => field.to_string(),
+        }
+    }
+
+    fn percentage_field(value: Option<f64>) -> String {
+        value.map_or(String::new(), |value| format!("{value:.2}"))
+    }
+
+    /// Write a single record, quoting the fields that contain a delimiter, a quote or a newline.
+    fn write_record(&self, out: &mut dyn Write, fields: &[String]) -> io::Result<()> {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                if field.contains([self.delimiter, '"', '\n', '\r']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.clone()
+                }
+            })
+            .collect();
+        writeln!(out, "{}", fields.join(&self.delimiter.to_string()))
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::{relative_path, LcovRenderer};
+use crate::changes::ChangedLines;
+use crate::{Lcov, LcovFile};
+
+/// Render the uncovered lines of an LCOV file as GitHub Actions workflow commands.
+///
+/// Every range of uncovered lines becomes a `::warning` annotation, which GitHub shows inline in
+/// the diff of a pull request. Unlike the other renderers, this renders the line data of an
+/// [`Lcov`] instead of a [`Report`](crate::Report).
+#[derive(Debug, Clone, Default)]
+pub struct Github {
+    /// Only annotate the lines that were changed, with the paths of the diff.
+    pub changed: Option<ChangedLines>,
+    /// The root of the repository, which is stripped from absolute paths.
+    pub root: Option<PathBuf>,
+    /// Annotate the functions that were never called instead of the uncovered lines.
+    pub only_functions: bool,
+}
+
+impl LcovRenderer for Github {
+    fn render(&self, lcov: &Lcov, out: &mut dyn Write) -> io::Result<()> {
+        for file in lcov.files() {
+            if self.only_functions {
+                self.render_functions(file, out)?;
+                continue;
+            }
+
+            let (path, ranges) = match &self.changed {
+                Some(changed) => match changed.get(file.name()) {
+                    Some((path, lines)) => {
+                        (path, file.uncovered_ranges(|line| lines.contains(&line)))
+                    }
+                    None => continue,
+                },
+                None => (
+                    relative_path(file.name(), self.root.as_deref()),
+                    file.uncovered_ranges(|_| true),
+                ),
+            };
+
+            for range in ranges {
+                writeln!(
+                    out,
+                    "::warning file={},line={},endLine={}::Uncovered lines",
+                    escape_property(path),
+                    range.start(),
+                    range.end(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Github {
+    fn render_functions(&self, file: &LcovFile, out: &mut dyn Write) -> io::Result<()> {
+        let (path, lines) = match &self.changed {
+            Some(changed) => match changed.get(file.name()) {
+                Some((path, lines)) => (path, Some(lines)),
+                None => return Ok(()),
+            },
+            None => (relative_path(file.name(), self.root.as_deref()), None),
+        };
+
+        for (line, name) in file.uncovered_functions() {
+            let Some(line) = line else {
+                continue;
+            };
+            if lines.is_some_and(|lines| !lines.contains(&line)) {
+                continue;
+            }
+            writeln!(
+                out,
+                "::warning file={},line={line}::Uncovered function {}",
+                escape_property(path),
+                escape_data(&name),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape the value of a property of a workflow command.
+fn escape_property(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
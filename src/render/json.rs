@@ -0,0 +1,88 @@
+use std::io::{self, Write};
+
+use serde_json::{json, Value};
+
+use super::Renderer;
+use crate::report::{Field, Report, Row, RowKind};
+
+/// Render a report as JSON.
+///
+/// Every row has an object per metric with `hit`, `found` and `percentage` fields, where the
+/// percentage is `null` when it is not applicable. For delta rows, the fields hold the
+/// differences. The values of extra columns are strings or numbers, and `null` when empty.
+#[derive(Debug, Clone, Default)]
+pub struct Json;
+
+impl Renderer for Json {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let groups: Vec<Value> = report
+            .groups
+            .iter()
+            .map(|group| {
+                let rows: Vec<Value> = group
+                    .rows
+                    .iter()
+                    .map(|row| Self::row(report, row))
+                    .collect();
+                json!({ "rows": rows })
+            })
+            .collect();
+
+        let report = json!({
+            "metrics": report.metrics.iter().map(|metric| metric.key()).collect::<Vec<_>>(),
+            "extra_columns": report.extra_columns,
+            "groups": groups,
+        });
+
+        serde_json::to_writer_pretty(&mut *out, &report)?;
+        writeln!(out)
+    }
+}
+
+impl Json {
+    fn row(report: &Report, row: &Row) -> Value {
+        let mut value = json!({
+            "name": row.name,
+            "kind": match row.kind {
+                RowKind::Item => "item",
+                RowKind::Total => "total",
+                RowKind::Delta { .. } => "delta",
+            },
+        });
+
+        for &metric in &report.metrics {
+            let counter = row.metrics.get(metric);
+            value[metric.key()] = match &row.kind {
+                RowKind::Item | RowKind::Total => json!({
+                    "hit": counter.hit,
+                    "found": counter.found,
+                    "percentage": counter.percentage().value(),
+                }),
+                RowKind::Delta { from } => {
+                    let from = from.get(metric);
+                    json!({
+                        "hit": counter.hit as i64 - from.hit as i64,
+                        "found": counter.found as i64 - from.found as i64,
+                        "percentage": from.percentage().delta(counter.percentage()),
+                    })
+                }
+            };
+        }
+
+        if !report.extra_columns.is_empty() {
+            let extra: Vec<Value> = row
+                .extra
+                .iter()
+                .map(|field| match field {
+                    Field::Empty => Value::Null,
+                    Field::Text(text) => json!(text),
+                    Field::Number(count) => json!(count),
+                    Field::Gain(gain) => json!(gain),
+                })
+                .collect();
+            value["extra"] = json!(extra);
+        }
+
+        value
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{self, Write};
+
+use super::{escape_xml, Renderer};
+use crate::metric::Percentage;
+use crate::report::{Report, Row, RowKind, Thresholds};
+
+/// Render a report as a JUnit XML test suite, so CI systems show coverage checks as tests.
+///
+/// Every metric of every row that is not a delta is a test case, with the row name as class
+/// name. A case fails when its coverage is below the high threshold, and is skipped when its
+/// coverage is not applicable.
+///
+/// A report without metrics, like [`Report::uncovered_functions`] or [`Report::hotspots`], lists
+/// uncovered code: every item row is a failing test case, with the [`Report::problem`] and the
+/// values of the extra columns in its message.
+#[derive(Debug, Clone, Default)]
+pub struct Junit {
+    /// The thresholds of the checks. Coverage below `high` fails.
+    pub thresholds: Thresholds,
+}
+
+impl Renderer for Junit {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let mut cases = vec![];
+        let mut failures = 0;
+        let mut skipped = 0;
+
+        for row in report.rows() {
+            if let RowKind::Delta { .. } = row.kind {
+                continue;
+            }
+
+            if report.metrics.is_empty() {
+                if let RowKind::Item = row.kind {
+                    failures += 1;
+                    cases.push(Self::uncovered_case(report, row));
+                }
+                continue;
+            }
+
+            for &metric in &report.metrics {
+                let name = escape_xml(&row.name);
+                let percentage = row.metrics.get(metric).percentage();
+                let mut case = format!(
+                    "    <testcase name=\"{}\" classname=\"{name}\"",
+                    metric.key()
+                );
+
+                if percentage == Percentage::NotApplicable {
+                    skipped += 1;
+                    case += ">\n      <skipped/>\n    </testcase>";
+                } else if percentage.is_below(self.thresholds.high) {
+                    failures += 1;
+                    let message = format!(
+                        "{} {} {percentage:.1} &lt; {}%",
+                        name,
+                        metric.key(),
+                        self.thresholds.high
+                    );
+                    case += &format!(
+                        ">\n      <failure message=\"{message}\" type=\"coverage\">{message}</failure>\n    </testcase>"
+                    );
+                } else {
+                    case += "/>";
+                }
+
+                cases.push(case);
+            }
+        }
+
+        let tests = cases.len();
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<testsuites name="{}" tests="{tests}" failures="{failures}" skipped="{skipped}">"#,
+            env!("CARGO_PKG_NAME")
+        )?;
+        writeln!(
+            out,
+            r#"  <testsuite name="coverage" tests="{tests}" failures="{failures}" skipped="{skipped}">"#
+        )?;
+        for case in cases {
+            writeln!(out, "{case}")?;
+        }
+        writeln!(out, "  </testsuite>")?;
+        writeln!(out, "</testsuites>")
+    }
+}
+
+impl Junit {
+    fn uncovered_case(report: &Report, row: &Row) -> String {
+        let name = escape_xml(&row.name);
+        let details: Vec<String> = report
+            .extra_columns
+            .iter()
+            .zip(&row.extra)
+            .map(|(column, value)| (column, value.to_string()))
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(column, value)| format!("{} {}", column.to_lowercase(), escape_xml(&value)))
+            .collect();
+        let problem = report.problem.as_deref().unwrap_or("is not fully covered");
+        let message = format!("{name} {} ({})", escape_xml(problem), details.join(", "));
+        format!(
+            "    <testcase name=\"{name}\" classname=\"uncovered\">\n      <failure message=\"{message}\" type=\"coverage\">{message}</failure>\n    </testcase>"
+        )
+    }
+}
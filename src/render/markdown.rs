@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+
+use super::{count_delta, percentage_delta, Renderer};
+use crate::report::{Report, Row, RowKind};
+
+/// Render a report as a GitHub flavored Markdown table.
+#[derive(Debug, Clone, Default)]
+pub struct Markdown;
+
+impl Renderer for Markdown {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let mut header = vec!["Name".to_string()];
+        let mut alignment = vec![":--"];
+        for metric in &report.metrics {
+            for column in ["Hit", "Total", "H/T"] {
+                header.push(format!("{} {column}", metric.name()));
+                alignment.push("--:");
+            }
+        }
+        for column in &report.extra_columns {
+            header.push(escape(column));
+            alignment.push("--:");
+        }
+
+        writeln!(out, "| {} |", header.join(" | "))?;
+        writeln!(out, "| {} |", alignment.join(" | "))?;
+
+        for row in report.rows() {
+            writeln!(out, "| {} |", Self::cells(report, row).join(" | "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Markdown {
+    fn cells(report: &Report, row: &Row) -> Vec<String> {
+        let mut cells = vec![match row.kind {
+            RowKind::Total => format!("**{}**", escape(&row.name)),
+            RowKind::Item | RowKind::Delta { .. } => escape(&row.name),
+        }];
+
+        for &metric in &report.metrics {
+            let counter = row.metrics.get(metric);
+            match &row.kind {
+                RowKind::Item | RowKind::Total => {
+                    cells.push(counter.hit.to_string());
+                    cells.push(counter.found.to_string());
+                    cells.push(counter.percentage().to_string());
+                }
+                RowKind::Delta { from } => {
+                    let from = from.get(metric);
+                    cells.push(count_delta(from.hit, counter.hit));
+                    cells.push(count_delta(from.found, counter.found));
+                    cells.push(percentage_delta(
+                        from.percentage().delta(counter.percentage()),
+                    ));
+                }
+            }
+        }
+
+        cells.extend(row.extra.iter().map(|extra| escape(&extra.to_string())));
+        cells
+    }
+}
+
+/// Escape the characters that would break a table cell.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
@@ -0,0 +1,458 @@
+//! Renderers that turn a [`Report`], or the line data of an LCOV file, into text.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::report::Report;
+use crate::Lcov;
+
+mod csv;
+mod github;
+mod json;
+mod junit;
+mod markdown;
+mod sarif;
+mod terminal;
+mod tracefile;
+
+pub use self::csv::Csv;
+pub use self::github::Github;
+pub use self::json::Json;
+pub use self::junit::Junit;
+pub use self::markdown::Markdown;
+pub use self::sarif::Sarif;
+pub use self::terminal::Terminal;
+pub use self::tracefile::Tracefile;
+
+/// An output format for a [`Report`].
+pub trait Renderer {
+    /// Render a report to a writer.
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Render a report to a string.
+    fn render_to_string(&self, report: &Report) -> String {
+        render_to_string(|out| self.render(report, out))
+    }
+}
+
+/// An output format for the line data of an [`Lcov`], like annotations of uncovered lines.
+pub trait LcovRenderer {
+    /// Render an LCOV file to a writer.
+    fn render(&self, lcov: &Lcov, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Render an LCOV file to a string.
+    fn render_to_string(&self, lcov: &Lcov) -> String {
+        render_to_string(|out| self.render(lcov, out))
+    }
+}
+
+/// Collect the output of a renderer in a string.
+fn render_to_string(render: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> String {
+    let mut out = vec![];
+    render(&mut out).expect("writing to a Vec does not fail");
+    String::from_utf8(out).expect("renderers only write UTF-8")
+}
+
+/// Format the difference between two counts, for example `+ 3` or `- 1`.
+///
+/// An unchanged count is formatted as an empty string.
+pub(crate) fn count_delta(from: usize, to: usize) -> String {
+    let diff = to as isize - from as isize;
+    match diff {
+        diff if diff > 0 => format!("+ {diff}"),
+        diff if diff < 0 => format!("- {}", diff.abs()),
+        _ => String::new(),
+    }
+}
+
+/// Format a difference in percentage points, for example `+ 1.25%` or `= 0.00%`.
+///
+/// A difference that is not applicable is formatted as `-`.
+pub(crate) fn percentage_delta(delta: Option<f64>) -> String {
+    match delta {
+        None => "-".to_string(),
+        Some(delta) if delta == 0. => format!("= {delta:.2}%"),
+        Some(delta) if delta > 0. => format!("+ {delta:.2}%"),
+        Some(delta) => format!("- {:.2}%", delta.abs()),
+    }
+}
+
+/// Escape text for XML content and attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Return the path of a source file relative to `root`, when it is inside `root`.
+pub(crate) fn relative_path<'a>(name: &'a str, root: Option<&Path>) -> &'a str {
+    root.and_then(|root| name.strip_prefix(root.to_str()?))
+        .and_then(|path| path.strip_prefix('/'))
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::{Counter, Metric, Metrics};
+    use crate::report::{Group, Row, Thresholds};
+
+    fn report() -> Report {
+        let from = Metrics {
+            lines: Counter { hit: 3, found: 6 },
+            ..Default::default()
+        };
+        let to = Metrics {
+            lines: Counter { hit: 4, found: 6 },
+            ..Default::default()
+        };
+
+        Report {
+            metrics: vec![Metric::Lines, Metric::Functions],
+            extra_columns: vec![],
+            problem: None,
+            groups: vec![Group {
+                rows: vec![
+                    Row::total("a.info", from),
+                    Row::total("b.info", to),
+                    Row::delta("diff", from, to),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_markdown() {
+        let output = Markdown.render_to_string(&report());
+        let lines: Vec<_> = output.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2], "| **a.info** | 3 | 6 | 50.00% | 0 | 0 | - |");
+        assert_eq!(lines[4], "| diff | + 1 |  | + 16.67% |  |  | - |");
+    }
+
+    #[test]
+    fn test_csv() {
+        let output = Csv::default().render_to_string(&report());
+        let lines: Vec<_> = output.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "name,kind,\
+             lines_hit,lines_total,lines_percentage,\
+             functions_hit,functions_total,functions_percentage,\
+             branches_hit,branches_total,branches_percentage,\
+             lines_hit_delta,lines_total_delta,lines_percentage_delta,\
+             functions_hit_delta,functions_total_delta,functions_percentage_delta,\
+             branches_hit_delta,branches_total_delta,branches_percentage_delta"
+        );
+        assert_eq!(lines[1], "a.info,total,3,6,50.00,0,0,,0,0,,,,,,,,,,");
+        assert_eq!(
+            lines[2],
+            "b.info,total,4,6,66.67,0,0,,0,0,,1,0,16.67,0,0,,0,0,"
+        );
+    }
+
+    #[test]
+    fn test_csv_paths() {
+        let before = "SF:/ws/a/src/lib.rs\nDA:1,0\nLF:1\nLH:0\nend_of_record\n\
+                      SF:/ws/b/src/lib.rs\nDA:1,1\nLF:1\nLH:1\nend_of_record\n";
+        let after = "SF:/ws/a/src/lib.rs\nDA:1,1\nLF:1\nLH:1\nend_of_record\n\
+                     SF:/ws/b/src/lib.rs\nDA:1,1\nLF:1\nLH:1\nend_of_record\n";
+        let before = crate::Lcov::from_reader(before.as_bytes()).unwrap();
+        let after = crate::Lcov::from_reader(after.as_bytes()).unwrap();
+        let report = Report::diff_all_files(&before, &after);
+
+        let csv = Csv {
+            root: Some("/ws".into()),
+            ..Csv::default()
+        };
+        let output = csv.render_to_string(&report);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("a/src/lib.rs,item,1,1,100.00,"));
+        assert!(lines[1].ends_with(",1,0,100.00,0,0,,0,0,"));
+        assert!(lines[2].starts_with("b/src/lib.rs,item,1,1,100.00,"));
+        assert!(lines[2].ends_with(",0,0,0.00,0,0,,0,0,"));
+
+        let output = Csv::default().render_to_string(&report);
+        assert!(output.contains("\n/ws/b/src/lib.rs,item,"));
+        assert_eq!(Report::diff_files(&before, &after).groups.len(), 2);
+    }
+
+    #[test]
+    fn test_tsv() {
+        let output = Csv::tsv().render_to_string(&report());
+        let lines: Vec<_> = output.lines().collect();
+
+        assert!(lines[0].starts_with("name\tkind\tlines_hit\tlines_total\t"));
+        assert!(lines[1].starts_with("a.info\ttotal\t3\t6\t50.00\t"));
+    }
+
+    #[test]
+    fn test_json() {
+        let output = Json.render_to_string(&report());
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        let rows = &value["groups"][0]["rows"];
+        assert_eq!(rows[0]["lines"]["percentage"], 50.);
+        assert!(rows[0]["functions"]["percentage"].is_null());
+        assert_eq!(rows[2]["kind"], "delta");
+        assert_eq!(rows[2]["lines"]["hit"], 1);
+    }
+
+    #[test]
+    fn test_github() {
+        let input = "SF:/repo/src/a.rs\nDA:1,1\nDA:2,0\nDA:4,0\nDA:5,1\nDA:6,0\nend_of_record\n";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+
+        let github = Github {
+            root: Some("/repo".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            github.render_to_string(&lcov),
+            "::warning file=src/a.rs,line=2,endLine=4::Uncovered lines\n\
+             ::warning file=src/a.rs,line=6,endLine=6::Uncovered lines\n"
+        );
+
+        let diff = "+++ b/src/a.rs\n@@ -3,0 +4,3 @@\n+a\n+b\n+c\n";
+        let github = Github {
+            changed: Some(crate::ChangedLines::from_reader(diff.as_bytes()).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            github.render_to_string(&lcov),
+            "::warning file=src/a.rs,line=4,endLine=4::Uncovered lines\n\
+             ::warning file=src/a.rs,line=6,endLine=6::Uncovered lines\n"
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let input = "\
+SF:/repo/src/a.rs
+FN:1,_RNvCs123_4test3foo
+FN:5,_RNvCs123_4test3bar
+FNDA:1,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3bar
+FNF:2
+FNH:1
+DA:1,1
+DA:2,0
+DA:5,0
+LF:3
+LH:1
+BRDA:1,0,0,1
+BRDA:1,0,1,0
+BRF:2
+BRH:1
+end_of_record
+";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+
+        let sarif = Sarif {
+            root: Some("/repo".into()),
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&sarif.render_to_string(&lcov)).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        let results = value["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0]["ruleId"], "uncovered-function");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "Function `test::bar` is not covered."
+        );
+
+        assert_eq!(results[1]["ruleId"], "uncovered-lines");
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/a.rs");
+        assert_eq!(location["region"]["startLine"], 2);
+        assert_eq!(location["region"]["endLine"], 5);
+
+        assert_eq!(results[2]["ruleId"], "uncovered-branches");
+        assert_eq!(
+            results[2]["message"]["text"],
+            "1 of 2 branches are not taken."
+        );
+    }
+
+    #[test]
+    fn test_junit() {
+        let junit = Junit {
+            thresholds: Thresholds {
+                low: 50.,
+                high: 60.,
+            },
+        };
+        let output = junit.render_to_string(&report());
+
+        assert!(
+            output.contains(r#"<testsuite name="coverage" tests="4" failures="1" skipped="2">"#)
+        );
+        assert!(
+            output.contains(r#"<failure message="a.info lines 50.0% &lt; 60%" type="coverage">"#)
+        );
+        assert!(output.contains(r#"<testcase name="lines" classname="b.info"/>"#));
+    }
+
+    #[test]
+    fn test_uncovered_functions() {
+        let input = "\
+SF:/repo/src/a.rs
+FN:5,_RNvCs123_4test3bar
+FN:1,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3bar
+FNF:2
+FNH:0
+end_of_record
+SF:/repo/src/b.rs
+FN:1,_RNvCs123_4test3baz
+FNDA:1,_RNvCs123_4test3baz
+FNF:1
+FNH:1
+end_of_record
+";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+        let report = Report::uncovered_functions(&lcov);
+
+        assert_eq!(
+            Csv::default().render_to_string(&report),
+            "name,kind,file,line\ntest::foo,item,src/a.rs,1\ntest::bar,item,src/a.rs,5\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&Json.render_to_string(&report)).unwrap();
+        assert_eq!(
+            json["groups"][0]["rows"][0]["extra"],
+            serde_json::json!(["src/a.rs", 1])
+        );
+        let output = Junit::default().render_to_string(&report);
+        assert!(output.contains(r#"tests="2" failures="2""#));
+        assert!(output.contains(r#"message="test::foo was never called (file src/a.rs, line 1)""#));
+
+        let github = Github {
+            root: Some("/repo".into()),
+            only_functions: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            github.render_to_string(&lcov),
+            "::warning file=src/a.rs,line=1::Uncovered function test::foo\n\
+             ::warning file=src/a.rs,line=5::Uncovered function test::bar\n"
+        );
+    }
+
+    #[test]
+    fn test_hotspots() {
+        let input = "\
+SF:/repo/src/a.rs
+FN:1,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3foo
+DA:1,0
+DA:2,0
+DA:3,1
+DA:4,1
+end_of_record
+";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+        let report = Report::hotspots(&lcov, 10);
+
+        assert_eq!(
+            Csv::default().render_to_string(&report),
+            "name,kind,file,uncovered,gain\n\
+             /repo/src/a.rs,item,src/a.rs,2,50.00\n\
+             test::foo,item,src/a.rs,2,50.00\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&Json.render_to_string(&report)).unwrap();
+        assert_eq!(
+            json["groups"][1]["rows"][0]["extra"],
+            serde_json::json!(["src/a.rs", 2, 50.])
+        );
+        assert!(Markdown
+            .render_to_string(&report)
+            .contains("| test::foo | src/a.rs | 2 | +50.00% |"));
+    }
+
+    #[test]
+    fn test_tracefile_round_trip() {
+        let input = "\
+TN:unit
+SF:/repo/src/a.rs
+VER:#1a2b3c
+FNL:0,1,4
+FNA:0,1,_RNvCs123_4test3foo
+FNL:1,6
+FNA:1,0,_RNvCs123_4test3bar
+FNL:2,8,9
+FNA:2,2,baz<int>
+FNA:2,1,baz<long>
+FNF:3
+FNH:2
+BRDA:2,0,0,1
+BRDA:2,0,1,-
+BRF:2
+BRH:1
+DA:1,1
+DA:2,1
+DA:6,0
+LF:3
+LH:2
+end_of_record
+";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+        let output = Tracefile.render_to_string(&lcov);
+
+        assert_eq!(
+            output,
+            "\
+TN:unit
+SF:/repo/src/a.rs
+VER:#1a2b3c
+FNL:0,8,9
+FNA:0,3,baz<int>
+FNA:0,0,baz<long>
+FN:1,4,_RNvCs123_4test3foo
+FN:6,_RNvCs123_4test3bar
+FNDA:1,_RNvCs123_4test3foo
+FNDA:0,_RNvCs123_4test3bar
+FNF:3
+FNH:2
+BRDA:2,0,0,1
+BRDA:2,0,1,-
+BRF:2
+BRH:1
+DA:1,1
+DA:2,1
+DA:6,0
+LF:3
+LH:2
+end_of_record
+"
+        );
+        assert_eq!(crate::Lcov::from_reader(output.as_bytes()).unwrap(), lcov);
+    }
+
+    #[test]
+    fn test_function_without_fn_record() {
+        let input = "SF:src/a.rs\nFNDA:0,foo\nDA:1,1\nend_of_record\n";
+        let lcov = crate::Lcov::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            lcov.files()[0].metrics().functions,
+            Counter { hit: 0, found: 1 }
+        );
+        assert_eq!(lcov.files()[0].uncovered_functions().len(), 1);
+        assert!(Tracefile
+            .render_to_string(&lcov)
+            .contains("FNDA:0,foo\nFNF:1\nFNH:0\n"));
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use super::{relative_path, LcovRenderer};
+use crate::metric::Metric;
+use crate::report::{Level, Thresholds};
+use crate::{Lcov, LcovFile};
+
+/// The rules of the results, in the order of their rule index, with the metric that decides
+/// their level.
+const RULES: [(&str, Metric, &str); 3] = [
+    (
+        "uncovered-lines",
+        Metric::Lines,
+        "Lines that are not covered by any test.",
+    ),
+    (
+        "uncovered-function",
+        Metric::Functions,
+        "A function that is not covered by any test.",
+    ),
+    (
+        "uncovered-branches",
+        Metric::Branches,
+        "Branches that are not taken by any test.",
+    ),
+];
+
+/// Render the uncovered code of an LCOV file as a SARIF 2.1.0 log.
+///
+/// Every range of uncovered lines, every uncovered function and every line with branches that are
+/// not taken is a result. The level of a result follows the coverage of its metric in the source
+/// file: `error` for low coverage, `warning` for medium coverage and `note` otherwise. Like
+/// [`Github`](super::Github), this renders the line data of an [`Lcov`].
+#[derive(Debug, Clone, Default)]
+pub struct Sarif {
+    /// The thresholds that decide the level of a result.
+    pub thresholds: Thresholds,
+    /// The root of the repository, which is stripped from absolute paths.
+    pub root: Option<PathBuf>,
+    /// Only report the uncovered functions.
+    pub only_functions: bool,
+}
+
+impl LcovRenderer for Sarif {
+    fn render(&self, lcov: &Lcov, out: &mut dyn Write) -> io::Result<()> {
+        let mut results = vec![];
+        for file in lcov.files() {
+            self.file_results(file, &mut results);
+        }
+
+        let rules: Vec<Value> = RULES
+            .iter()
+            .map(|(id, _, description)| {
+                json!({
+                    "id": id,
+                    "shortDescription": { "text": description },
+                })
+            })
+            .collect();
+
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+
+        serde_json::to_writer_pretty(&mut *out, &log)?;
+        writeln!(out)
+    }
+}
+
+impl Sarif {
+    fn file_results(&self, file: &LcovFile, results: &mut Vec<Value>) {
+        let uri = self.uri(file.name());
+
+        for (line, name) in file.uncovered_functions() {
+            let Some(line) = line else {
+                continue;
+            };
+            results.push(self.result(
+                file,
+                1,
+                &format!("Function `{name}` is not covered."),
+                &uri,
+                line..=line,
+            ));
+        }
+        if self.only_functions {
+            return;
+        }
+
+        for range in file.uncovered_ranges(|_| true) {
+            let message = if range.start() == range.end() {
+                format!("Line {} is not covered.", range.start())
+            } else {
+                format!("Lines {}-{} are not covered.", range.start(), range.end())
+            };
+            results.push(self.result(file, 0, &message, &uri, range));
+        }
+
+        let mut branches: Vec<(usize, usize, usize)> = vec![];
+        for (&(line, _, _), &taken) in file.branch_hits() {
+            let taken = taken.unwrap_or(0) > 0;
+            match branches.last_mut() {
+                Some((last, found, not_taken)) if *last == line => {
+                    *found += 1;
+                    *not_taken += usize::from(!taken);
+                }
+                _ => branches.push((line, 1, usize::from(!taken))),
+            }
+        }
+        for (line, found, not_taken) in branches {
+            if not_taken > 0 {
+                results.push(self.result(
+                    file,
+                    2,
+                    &format!("{not_taken} of {found} branches are not taken."),
+                    &uri,
+                    line..=line,
+                ));
+            }
+        }
+    }
+
+    fn result(
+        &self,
+        file: &LcovFile,
+        rule: usize,
+        message: &str,
+        uri: &str,
+        lines: RangeInclusive<usize>,
+    ) -> Value {
+        let (id, metric, _) = RULES[rule];
+        let level = match self
+            .thresholds
+            .level(file.metrics().get(metric).percentage())
+        {
+            Some(Level::Low) => "error",
+            Some(Level::Medium) => "warning",
+            Some(Level::High) | None => "note",
+        };
+
+        json!({
+            "ruleId": id,
+            "ruleIndex": rule,
+            "level": level,
+            "message": { "text": message },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": uri },
+                    "region": {
+                        "startLine": lines.start(),
+                        "endLine": lines.end(),
+                    },
+                },
+            }],
+        })
+    }
+
+    /// Return the URI of a source file, relative to the root when it is inside the root.
+    fn uri(&self, name: &str) -> String {
+        let path = relative_path(name, self.root.as_deref());
+        if path.starts_with('/') {
+            format!("file://{path}")
+        } else {
+            path.to_string()
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::{self, Write};
+
+use colored::*;
+use prettytable::{format::consts::FORMAT_CLEAN, format::Alignment, Cell, Table};
+
+use super::{count_delta, percentage_delta, Renderer};
+use crate::metric::{Metric, Percentage};
+use crate::report::{Level, Report, Row, RowKind, Thresholds};
+
+/// Render a report as a table for the terminal, with colored percentages.
+#[derive(Debug, Clone, Default)]
+pub struct Terminal {
+    /// The thresholds that decide the color of a percentage.
+    pub thresholds: Thresholds,
+}
+
+impl Renderer for Terminal {
+    fn render(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let mut table = Table::new();
+        table.set_format(*FORMAT_CLEAN);
+        table.set_titles(Self::title_row(report));
+        table.add_row(Self::sub_title_row(report));
+
+        for row in report.rows() {
+            table.add_row(self.row(row, &report.metrics));
+        }
+
+        table.print(out)?;
+        Ok(())
+    }
+}
+
+impl Terminal {
+    fn row(&self, row: &Row, metrics: &[Metric]) -> prettytable::Row {
+        let name = match row.kind {
+            RowKind::Item => Cell::new(&row.name),
+            RowKind::Total | RowKind::Delta { .. } => Cell::new_align(&row.name, Alignment::RIGHT),
+        };
+
+        let mut cells = vec![name];
+        for &metric in metrics {
+            let counter = row.metrics.get(metric);
+            cells.push(Cell::new("│"));
+
+            match &row.kind {
+                RowKind::Item | RowKind::Total => {
+                    cells.push(Cell::new_align(&counter.hit.to_string(), Alignment::RIGHT));
+                    cells.push(Cell::new_align(
+                        &counter.found.to_string(),
+                        Alignment::RIGHT,
+                    ));
+                    cells.push(Cell::new_align(
+                        &self.color_percentage(counter.percentage()),
+                        Alignment::RIGHT,
+                    ));
+                }
+                RowKind::Delta { from } => {
+                    let from = from.get(metric);
+                    cells.push(Cell::new_align(
+                        &count_delta(from.hit, counter.hit),
+                        Alignment::RIGHT,
+                    ));
+                    cells.push(Cell::new_align(
+                        &count_delta(from.found, counter.found),
+                        Alignment::RIGHT,
+                    ));
+                    cells.push(Cell::new_align(
+                        &Self::color_percentage_delta(
+                            from.percentage().delta(counter.percentage()),
+                        ),
+                        Alignment::RIGHT,
+                    ));
+                }
+            }
+        }
+
+        for extra in &row.extra {
+            cells.push(Cell::new("│"));
+            cells.push(Cell::new_align(&extra.to_string(), Alignment::RIGHT));
+        }
+
+        prettytable::Row::new(cells)
+    }
+
+    fn color_percentage_delta(value: Option<f64>) -> String {
+        let p = percentage_delta(value);
+        match value {
+            None => p,
+            Some(0.) => p.yellow().to_string(),
+            Some(value) if value > 0. => p.green().to_string(),
+            Some(_) => p.red().to_string(),
+        }
+    }
+
+    fn color_percentage(&self, value: Percentage) -> String {
+        let p = value.to_string();
+        format!(
+            "{}",
+            match self.thresholds.level(value) {
+                None => p.normal(),
+                Some(Level::Low) => p.red(),
+                Some(Level::Medium) => p.yellow(),
+                Some(Level::High) => p.green(),
+            }
+        )
+    }
+
+    fn title_row(report: &Report) -> prettytable::Row {
+        let mut cells = vec![Cell::new(""), Cell::new("")];
+        for (i, metric) in report.metrics.iter().enumerate() {
+            if i > 0 {
+                cells.push(Cell::new(""));
+            }
+            let mut title = Cell::new_align(metric.name(), Alignment::CENTER);
+            title.set_hspan(3);
+            cells.push(title);
+        }
+        for _ in &report.extra_columns {
+            cells.push(Cell::new(""));
+            cells.push(Cell::new(""));
+        }
+        prettytable::Row::new(cells)
+    }
+
+    fn sub_title_row(report: &Report) -> prettytable::Row {
+        let mut cells = vec![Cell::new("")];
+        for _ in &report.metrics {
+            cells.push(Cell::new("│"));
+            cells.push(Cell::new("Hit"));
+            cells.push(Cell::new("Total"));
+            cells.push(Cell::new("H/T"));
+        }
+        for column in &report.extra_columns {
+            cells.push(Cell::new("│"));
+            cells.push(Cell::new(column));
+        }
+        prettytable::Row::new(cells)
+    }
+}
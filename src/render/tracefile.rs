@@ -0,0 +1,101 @@
+use std::io::{self, Write};
+
+use super::LcovRenderer;
+use crate::{Lcov, LcovFile};
+
+/// Render an LCOV file as an LCOV tracefile again, with a record per source file.
+///
+/// This writes the coverage after filters, exclusions and merging, so the result can be passed to
+/// other LCOV tools. Functions are written as `FN:` and `FNDA:` records, with the end line of
+/// LCOV 2.x in the `FN:` record when it is known, and functions with aliases as `FNL:` and `FNA:`
+/// records. Like [`Github`](super::Github), this renders
+/// the line data of an [`Lcov`].
+#[derive(Debug, Clone, Default)]
+pub struct Tracefile;
+
+impl LcovRenderer for Tracefile {
+    fn render(&self, lcov: &Lcov, out: &mut dyn Write) -> io::Result<()> {
+        for file in lcov.files() {
+            Self::record(file, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tracefile {
+    fn record(file: &LcovFile, out: &mut dyn Write) -> io::Result<()> {
+        // A merged record of several test names can only be written without a test name.
+        if let [test] = file.tests() {
+            writeln!(out, "TN:{}", test.name())?;
+        }
+        writeln!(out, "SF:{}", file.name())?;
+        if let Some(version) = file.version() {
+            writeln!(out, "VER:{version}")?;
+        }
+
+        let mut functions: Vec<(Option<usize>, &str, usize)> = file
+            .function_hits()
+            .iter()
+            .map(|(name, &hits)| {
+                (
+                    file.function_lines().get(name).copied(),
+                    name.as_str(),
+                    hits,
+                )
+            })
+            .collect();
+        functions.sort_by(|a, b| (a.0.is_none(), a).cmp(&(b.0.is_none(), b)));
+        // A function with aliases is written as an `FNL:` record with an `FNA:` record per alias.
+        // Its hits are written for its own name only, because they are summed over the aliases
+        // when the record is read again.
+        let (aliased, functions): (Vec<_>, Vec<_>) =
+            functions.into_iter().partition(|&(line, name, _)| {
+                line.is_some() && file.function_aliases().contains_key(name)
+            });
+        for (index, &(line, name, hits)) in aliased.iter().enumerate() {
+            let line = line.unwrap_or_default();
+            match file.function_end_lines().get(name) {
+                Some(end_line) => writeln!(out, "FNL:{index},{line},{end_line}")?,
+                None => writeln!(out, "FNL:{index},{line}")?,
+            }
+            writeln!(out, "FNA:{index},{hits},{name}")?;
+            for alias in &file.function_aliases()[name] {
+                writeln!(out, "FNA:{index},0,{alias}")?;
+            }
+        }
+        for &(line, name, _) in &functions {
+            let Some(line) = line else {
+                continue;
+            };
+            match file.function_end_lines().get(name) {
+                Some(end_line) => writeln!(out, "FN:{line},{end_line},{name}")?,
+                None => writeln!(out, "FN:{line},{name}")?,
+            }
+        }
+        for &(_, name, hits) in &functions {
+            writeln!(out, "FNDA:{hits},{name}")?;
+        }
+        let metrics = file.metrics();
+        writeln!(out, "FNF:{}", metrics.functions.found)?;
+        writeln!(out, "FNH:{}", metrics.functions.hit)?;
+
+        for ((line, block, branch), &taken) in file.branch_hits() {
+            match taken {
+                Some(taken) => writeln!(out, "BRDA:{line},{block},{branch},{taken}")?,
+                None => writeln!(out, "BRDA:{line},{block},{branch},-")?,
+            }
+        }
+        if metrics.branches.found > 0 {
+            writeln!(out, "BRF:{}", metrics.branches.found)?;
+            writeln!(out, "BRH:{}", metrics.branches.hit)?;
+        }
+
+        for (line, hits) in file.line_hits() {
+            writeln!(out, "DA:{line},{hits}")?;
+        }
+        writeln!(out, "LF:{}", metrics.lines.found)?;
+        writeln!(out, "LH:{}", metrics.lines.hit)?;
+
+        writeln!(out, "end_of_record")
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use std::collections::BTreeMap;
+
+use crate::compare::format_ranges;
+use crate::history::sparkline;
+use crate::lcov_file::display_name;
+use crate::metric::{Metric, Metrics, Percentage};
+use crate::{History, HistoryEntry, Lcov, LcovSummary};
+
+/// The coverage percentages that divide low, medium and high coverage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Coverage below this percentage is low.
+    pub low: f64,
+    /// Coverage below this percentage, but not below `low`, is medium.
+    pub high: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            low: 70.,
+            high: 80.,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Return the level of a percentage, or `None` when it is not applicable.
+    pub fn level(&self, percentage: Percentage) -> Option<Level> {
+        if percentage == Percentage::NotApplicable {
+            None
+        } else if percentage.is_below(self.low) {
+            Some(Level::Low)
+        } else if percentage.is_below(self.high) {
+            Some(Level::Medium)
+        } else {
+            Some(Level::High)
+        }
+    }
+}
+
+/// The level of a coverage percentage, according to the [`Thresholds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Below the low threshold.
+    Low,
+    /// Below the high threshold, but not below the low threshold.
+    Medium,
+    /// Not below the high threshold.
+    High,
+}
+
+/// The kind of a row in a [`Report`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowKind {
+    /// A single item, like a source file or a test name.
+    Item,
+    /// The totals of an LCOV file.
+    Total,
+    /// The difference from `from` to the metrics of the row.
+    Delta {
+        /// The metrics that the row is compared to.
+        from: Metrics,
+    },
+}
+
+/// The value of an extra column of a [`Row`].
+///
+/// Numbers are kept as numbers, so every renderer can format them for its output, like a gain
+/// with a `%` sign in a table and without one in CSV.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    /// No value.
+    Empty,
+    /// A text, like the name of a source file.
+    Text(String),
+    /// A whole number, like a line number or a number of lines.
+    Number(usize),
+    /// A gain in percentage points, like the rise of the total line coverage.
+    Gain(f64),
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Empty => Ok(()),
+            Field::Text(text) => f.write_str(text),
+            Field::Number(count) => write!(f, "{count}"),
+            Field::Gain(gain) => write!(f, "+{gain:.2}%"),
+        }
+    }
+}
+
+impl From<String> for Field {
+    fn from(text: String) -> Self {
+        Field::Text(text)
+    }
+}
+
+impl From<&str> for Field {
+    fn from(text: &str) -> Self {
+        Field::Text(text.to_string())
+    }
+}
+
+/// A row of a [`Report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// The name of the row, like the path of a source file.
+    pub name: String,
+    /// The path of the source file of the row, as it appears in its `SF:` record.
+    ///
+    /// The name of a source file is shortened for display, so this is the one to use when the
+    /// path has to be unique.
+    pub path: Option<String>,
+    /// The kind of the row.
+    pub kind: RowKind,
+    /// The counters of the row.
+    pub metrics: Metrics,
+    /// The values of the extra columns of the report.
+    pub extra: Vec<Field>,
+}
+
+impl Row {
+    /// Create a row for a source file, named by its display name.
+    pub fn file(path: &str, metrics: Metrics) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            ..Self::item(display_name(path), metrics)
+        }
+    }
+
+    /// Create a row for a single item.
+    pub fn item(name: impl Into<String>, metrics: Metrics) -> Self {
+        Self {
+            name: name.into(),
+            path: None,
+            kind: RowKind::Item,
+            metrics,
+            extra: vec![],
+        }
+    }
+
+    /// Create a row with the totals of an LCOV file.
+    pub fn total(name: impl Into<String>, metrics: Metrics) -> Self {
+        Self {
+            name: name.into(),
+            path: None,
+            kind: RowKind::Total,
+            metrics,
+            extra: vec![],
+        }
+    }
+
+    /// Create a row with the difference between two sets of counters.
+    pub fn delta(name: impl Into<String>, from: Metrics, to: Metrics) -> Self {
+        Self {
+            name: name.into(),
+            path: None,
+            kind: RowKind::Delta { from },
+            metrics: to,
+            extra: vec![],
+        }
+    }
+}
+
+/// A group of rows that belong together, like a source file and its diff.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    /// The rows of the group.
+    pub rows: Vec<Row>,
+}
+
+/// A row whose coverage changed between two reports, as returned by [`Report::changes_since`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// The name of the row.
+    pub name: String,
+    /// The kind of the row, either [`RowKind::Item`] or [`RowKind::Total`].
+    pub kind: RowKind,
+    /// The metrics whose coverage changed, with the change in percentage points.
+    pub deltas: Vec<(Metric, f64)>,
+}
+
+/// The computed content of a coverage report, independent of how it is rendered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    /// The metrics that are shown, in order.
+    pub metrics: Vec<Metric>,
+    /// The headers of extra columns that follow the metrics.
+    pub extra_columns: Vec<String>,
+    /// What the item rows of a report without metrics have in common, like `was never called`.
+    ///
+    /// Renderers that list the rows as problems, like [`Junit`](crate::render::Junit), use it in
+    /// their messages, and say that the code `is not fully covered` without it.
+    pub problem: Option<String>,
+    /// The groups of rows.
+    pub groups: Vec<Group>,
+}
+
+impl Report {
+    /// Create a report of the summary of an LCOV file.
+    pub fn summary(name: &Path, summary: &LcovSummary) -> Self {
+        Self {
+            metrics: shown_metrics(&[summary]),
+            extra_columns: vec![],
+            problem: None,
+            groups: vec![Group {
+                rows: vec![Row::total(name.to_string_lossy(), summary.metrics)],
+            }],
+        }
+    }
+
+    /// Create a report of the summaries of two LCOV files and their difference.
+    pub fn diff_summary(
+        name: &Path,
+        summary: &LcovSummary,
+        other_name: &Path,
+        other: &LcovSummary,
+    ) -> Self {
+        Self {
+            metrics: shown_metrics(&[summary, other]),
+            extra_columns: vec![],
+            problem: None,
+            groups: vec![Group {
+                rows: vec![
+                    Row::total(name.to_string_lossy(), summary.metrics),
+                    Row::total(other_name.to_string_lossy(), other.metrics),
+                    Row::delta("diff", summary.metrics, other.metrics),
+                ],
+            }],
+        }
+    }
+
+    /// Create a report with every source file of an LCOV file, followed by the totals.
+    pub fn files(lcov: &Lcov) -> Self {
+        let summary = lcov.summary();
+
+        let mut files = Group::default();
+        for file in lcov.files() {
+            files.rows.push(Row::file(file.name(), file.metrics));
+        }
+
+        Self {
+            metrics: shown_metrics(&[&summary]),
+            extra_columns: vec![],
+            problem: None,
+            groups: vec![
+                files,
+                Group {
+                    rows: vec![Row::total(lcov.name().to_string_lossy(), summary.metrics)],
+                },
+            ],
+        }
+    }
+
+    /// Create a report with the source files whose coverage differs between two LCOV files,
+    /// followed by the totals of both files and their difference.
+    ///
+    /// Every changed file is a group with the coverage in `other` and its difference.
+    pub fn diff_files(lcov: &Lcov, other: &Lcov) -> Self {
+        Self::diff_files_with(lcov, other, true)
+    }
+
+    /// Create a report like [`Report::diff_files`], but with every source file of both LCOV
+    /// files, including the ones whose coverage did not change.
+    pub fn diff_all_files(lcov: &Lcov, other: &Lcov) -> Self {
+        Self::diff_files_with(lcov, other, false)
+    }
+
+    fn diff_files_with(lcov: &Lcov, other: &Lcov, changed_only: bool) -> Self {
+        let summary = lcov.summary();
+        let other_summary = other.summary();
+
+        let files: HashMap<&str, &Metrics> = lcov
+            .files()
+            .iter()
+            .map(|file| (file.name(), &file.metrics))
+            .collect();
+        let other_files: HashMap<&str, &Metrics> = other
+            .files()
+            .iter()
+            .map(|file| (file.name(), &file.metrics))
+            .collect();
+
+        let names = lcov.files().iter().map(|file| file.name()).chain(
+            other
+                .files()
+                .iter()
+                .map(|file| file.name())
+                .filter(|name| !files.contains_key(name)),
+        );
+
+        let mut groups = vec![];
+        for name in names {
+            let from = files.get(name).copied().copied().unwrap_or_default();
+            let to = other_files.get(name).copied().copied().unwrap_or_default();
+            if from != to || !changed_only {
+                groups.push(Group {
+                    rows: vec![Row::file(name, to), Row::delta("", from, to)],
+                });
+            }
+        }
+
+        groups.push(Group {
+            rows: vec![
+                Row::total(lcov.name().to_string_lossy(), summary.metrics),
+                Row::total(other.name().to_string_lossy(), other_summary.metrics),
+                Row::delta("diff", summary.metrics, other_summary.metrics),
+            ],
+        });
+
+        Self {
+            metrics: shown_metrics(&[&summary, &other_summary]),
+            extra_columns: vec![],
+            problem: None,
+            groups,
+        }
+    }
+
+    /// Create a report with the coverage per test name of an LCOV file, followed by the totals.
+    pub fn tests(lcov: &Lcov) -> Self {
+        let summary = lcov.summary();
+
+        let mut tests = Group::default();
+        for test in lcov.test_summaries() {
+            let mut row = Row::item(test.name, test.metrics);
+            row.extra.push(Field::Number(test.unique_lines));
+            tests.rows.push(row);
+        }
+
+        let mut total = Row::total(lcov.name().to_string_lossy(), summary.metrics);
+        total.extra.push(Field::Empty);
+
+        Self {
+            metrics: shown_metrics(&[&summary]),
+            extra_columns: vec!["Unique lines".to_string()],
+            problem: None,
+            groups: vec![tests, Group { rows: vec![total] }],
+        }
+    }
+
+    /// Create a report of the lines that are newly covered and newly uncovered in another LCOV
+    /// file, with a row per source file whose line coverage differs.
+    ///
+    /// The report has no metrics. The line ranges are in the extra columns, like `3-5, 9`.
+    pub fn compare(lcov: &Lcov, other: &Lcov) -> Self {
+        let rows: Vec<Row> = lcov
+            .compare(other)
+            .iter()
+            .map(|comparison| {
+                let mut row = Row::file(comparison.name(), Metrics::default());
+                row.extra
+                    .push(format_ranges(comparison.newly_covered()).into());
+                row.extra
+                    .push(format_ranges(comparison.newly_uncovered()).into());
+                row
+            })
+            .collect();
+
+        Self {
+            metrics: vec![],
+            extra_columns: vec!["Newly covered".to_string(), "Newly uncovered".to_string()],
+            problem: None,
+            groups: if rows.is_empty() {
+                vec![]
+            } else {
+                vec![Group { rows }]
+            },
+        }
+    }
+
+    /// Create a report of the coverage over time in a history, with a group of entries, a group
+    /// of directories and the totals.
+    ///
+    /// An entry is named by its date, with its commit in the first extra column. The directories
+    /// and the totals have the coverage of the last entry, and a sparkline of every metric over
+    /// all entries in the other extra columns.
+    pub fn trend(history: &History) -> Self {
+        let entries = history.entries();
+        let metrics: Vec<Metric> = Metric::ALL
+            .into_iter()
+            .filter(|&metric| {
+                matches!(metric, Metric::Lines | Metric::Functions)
+                    || entries.iter().any(|entry| entry.metrics.has(metric))
+            })
+            .collect();
+
+        let trend_row = |row: &mut Row, history: Vec<Metrics>| {
+            row.extra.push(Field::Empty);
+            for &metric in &metrics {
+                let percentages: Vec<Percentage> = history
+                    .iter()
+                    .map(|metrics| metrics.get(metric).percentage())
+                    .collect();
+                row.extra.push(sparkline(&percentages).into());
+            }
+        };
+
+        let mut commits = Group::default();
+        for entry in entries {
+            let mut row = Row::item(&entry.date, entry.metrics);
+            row.extra
+                .push(Field::Text(entry.commit.chars().take(8).collect()));
+            row.extra.extend(metrics.iter().map(|_| Field::Empty));
+            commits.rows.push(row);
+        }
+
+        let directories: Vec<BTreeMap<String, Metrics>> =
+            entries.iter().map(HistoryEntry::directories).collect();
+        let mut names: Vec<&String> = directories.iter().flat_map(|d| d.keys()).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        let mut groups = vec![commits];
+        let mut group = Group::default();
+        for name in names {
+            let history: Vec<Metrics> = directories
+                .iter()
+                .map(|d| d.get(name).copied().unwrap_or_default())
+                .collect();
+            let mut row = Row::item(name, history.last().copied().unwrap_or_default());
+            trend_row(&mut row, history);
+            group.rows.push(row);
+        }
+        groups.push(group);
+
+        let history: Vec<Metrics> = entries.iter().map(|entry| entry.metrics).collect();
+        let mut total = Row::total("total", history.last().copied().unwrap_or_default());
+        trend_row(&mut total, history);
+        groups.push(Group { rows: vec![total] });
+
+        let mut extra_columns = vec!["Commit".to_string()];
+        extra_columns.extend(
+            metrics
+                .iter()
+                .map(|metric| format!("{} trend", metric.name())),
+        );
+        Self {
+            metrics,
+            extra_columns,
+            problem: None,
+            groups,
+        }
+    }
+
+    /// Create a report of the functions that were never called, with a group per source file.
+    ///
+    /// The report has no metrics. Every row is a function, with its demangled name, the source
+    /// file and the line of its `FN:` record in the extra columns.
+    pub fn uncovered_functions(lcov: &Lcov) -> Self {
+        let mut groups = vec![];
+        for file in lcov.files() {
+            let rows: Vec<Row> = file
+                .uncovered_functions()
+                .into_iter()
+                .map(|(line, name)| {
+                    let mut row = Row::item(name, Metrics::default());
+                    row.extra.push(file.display_name().into());
+                    row.extra.push(line.map_or(Field::Empty, Field::Number));
+                    row
+                })
+                .collect();
+            if !rows.is_empty() {
+                groups.push(Group { rows });
+            }
+        }
+
+        Self {
+            metrics: vec![],
+            extra_columns: vec!["File".to_string(), "Line".to_string()],
+            problem: Some("was never called".to_string()),
+            groups,
+        }
+    }
+
+    /// Create a report of the `limit` source files and functions whose uncovered lines weigh the
+    /// most on the total line coverage, with a group of files and a group of functions.
+    ///
+    /// The report has no metrics. The source file, the number of uncovered lines and the gain in
+    /// total line coverage are in the extra columns.
+    pub fn hotspots(lcov: &Lcov, limit: usize) -> Self {
+        let files = lcov.hotspots();
+        let functions = lcov.function_hotspots();
+
+        let mut groups = vec![];
+        for hotspots in [files, functions] {
+            let rows: Vec<Row> = hotspots
+                .iter()
+                .take(limit)
+                .map(|hotspot| {
+                    let mut row = match &hotspot.function {
+                        Some(function) => Row::item(function, Metrics::default()),
+                        None => Row::file(&hotspot.file, Metrics::default()),
+                    };
+                    row.extra = vec![
+                        hotspot.display_name().into(),
+                        Field::Number(hotspot.uncovered),
+                        Field::Gain(hotspot.gain),
+                    ];
+                    row
+                })
+                .collect();
+            if !rows.is_empty() {
+                groups.push(Group { rows });
+            }
+        }
+
+        Self {
+            metrics: vec![],
+            extra_columns: vec![
+                "File".to_string(),
+                "Uncovered".to_string(),
+                "Gain".to_string(),
+            ],
+            problem: Some("is not fully covered".to_string()),
+            groups,
+        }
+    }
+
+    /// Return all rows of the report.
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.groups.iter().flat_map(|group| &group.rows)
+    }
+
+    /// Return the rows whose coverage changed since a previous report, with the totals first.
+    ///
+    /// Rows are matched by their name and kind, and rows that only exist in one report or that
+    /// are differences themselves are skipped. Changes that round to 0.00 percentage points are
+    /// left out.
+    pub fn changes_since(&self, previous: &Report) -> Vec<Change> {
+        let mut changes: Vec<Change> = self
+            .rows()
+            .filter(|row| matches!(row.kind, RowKind::Item | RowKind::Total))
+            .filter_map(|row| {
+                let before = previous
+                    .rows()
+                    .find(|before| before.name == row.name && before.kind == row.kind)?;
+                let deltas: Vec<(Metric, f64)> = Metric::ALL
+                    .into_iter()
+                    .filter_map(|metric| {
+                        let delta = before
+                            .metrics
+                            .get(metric)
+                            .percentage()
+                            .delta(row.metrics.get(metric).percentage())?;
+                        (delta.abs() >= 0.005).then_some((metric, delta))
+                    })
+                    .collect();
+                (!deltas.is_empty()).then(|| Change {
+                    name: row.name.clone(),
+                    kind: row.kind.clone(),
+                    deltas,
+                })
+            })
+            .collect();
+        changes.sort_by_key(|change| change.kind != RowKind::Total);
+        changes
+    }
+}
+
+/// Return the metrics that should be displayed for the given summaries.
+///
+/// Lines and functions are always shown, other metrics only when one of the inputs reported them.
+fn shown_metrics(summaries: &[&LcovSummary]) -> Vec<Metric> {
+    Metric::ALL
+        .into_iter()
+        .filter(|&metric| {
+            matches!(metric, Metric::Lines | Metric::Functions)
+                || summaries.iter().any(|s| s.metrics.has(metric))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_since() {
+        let before = "SF:/src/a.rs\nDA:1,1\nDA:2,0\nLF:2\nLH:1\nend_of_record\n\
+                      SF:/src/b.rs\nDA:1,1\nLF:1\nLH:1\nend_of_record\n\
+                      SF:/src/c.rs\nDA:1,0\nLF:1\nLH:0\nend_of_record\n";
+        let after = "SF:/src/a.rs\nDA:1,1\nDA:2,1\nLF:2\nLH:2\nend_of_record\n\
+                     SF:/src/b.rs\nDA:1,1\nLF:1\nLH:1\nend_of_record\n\
+                     SF:/src/d.rs\nDA:1,0\nLF:1\nLH:0\nend_of_record\n";
+        let before = Report::files(&Lcov::from_reader(before.as_bytes()).unwrap());
+        let after = Report::files(&Lcov::from_reader(after.as_bytes()).unwrap());
+
+        let changes = after.changes_since(&before);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind, RowKind::Total);
+        assert_eq!(changes[0].deltas.len(), 1);
+        assert_eq!(changes[0].deltas[0].0, Metric::Lines);
+        assert!((changes[0].deltas[0].1 - 25.).abs() < 1e-9);
+        assert_eq!(changes[1].name, "src/a.rs");
+        assert_eq!(changes[1].kind, RowKind::Item);
+        assert_eq!(changes[1].deltas, vec![(Metric::Lines, 50.)]);
+
+        assert!(after.changes_since(&after).is_empty());
+    }
+
+    #[test]
+    fn test_compare() {
+        let lcov = "SF:/src/a.rs\nDA:1,1\nDA:2,0\nDA:3,0\nend_of_record\n\
+                    SF:/src/b.rs\nDA:1,1\nend_of_record\n";
+        let other = "SF:/src/a.rs\nDA:1,0\nDA:2,1\nDA:3,1\nend_of_record\n\
+                     SF:/src/b.rs\nDA:1,1\nend_of_record\n";
+        let lcov = Lcov::from_reader(lcov.as_bytes()).unwrap();
+        let other = Lcov::from_reader(other.as_bytes()).unwrap();
+
+        let report = Report::compare(&lcov, &other);
+        assert!(report.metrics.is_empty());
+        let rows: Vec<&Row> = report.rows().collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].name, "src/a.rs");
+        assert_eq!(rows[0].extra, ["2-3".into(), "1".into()]);
+
+        assert!(Report::compare(&lcov, &lcov).groups.is_empty());
+    }
+
+    #[test]
+    fn test_trend() {
+        let entry = |commit: &str, hit| {
+            let input = format!("SF:/repo/src/a.rs\nLF:4\nLH:{hit}\nend_of_record\n");
+            let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+            HistoryEntry::new(commit, "2024-05-01", &lcov).to_json()
+        };
+        let lines = [entry("0123456789", 1), entry("abcdef", 3)].join("\n");
+        let history = History::from_reader(lines.as_bytes()).unwrap();
+
+        let report = Report::trend(&history);
+        assert_eq!(report.metrics, [Metric::Lines, Metric::Functions]);
+        assert_eq!(report.groups.len(), 3);
+        assert_eq!(
+            report.groups[0].rows[0].extra,
+            ["01234567".into(), Field::Empty, Field::Empty]
+        );
+        assert_eq!(report.groups[1].rows[0].name, "src");
+        assert_eq!(
+            report.groups[1].rows[0].extra,
+            [Field::Empty, "▁█".into(), "  ".into()]
+        );
+        let total = &report.groups[2].rows[0];
+        assert_eq!(total.kind, RowKind::Total);
+        assert_eq!(total.metrics.lines.hit, 3);
+    }
+}
@@ -0,0 +1,523 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+use crate::metric::{Metric, Metrics};
+use crate::render::relative_path;
+use crate::report::Thresholds;
+use crate::{Lcov, LcovFile};
+
+/// The number of rows that page up and page down move when the height is not known yet.
+const DEFAULT_PAGE: usize = 10;
+
+/// A directory or source file in the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Node {
+    /// The name to display, which can be several directories that only contain each other.
+    pub(crate) name: String,
+    pub(crate) depth: usize,
+    pub(crate) kind: NodeKind,
+    /// The counters of the file, or the totals of the files in the directory.
+    pub(crate) metrics: Metrics,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Directory,
+    /// A source file, with its index in the files of the LCOV file.
+    File(usize),
+}
+
+/// A directory while the tree is built.
+#[derive(Default)]
+struct Directory {
+    directories: BTreeMap<String, Directory>,
+    files: BTreeMap<String, usize>,
+    metrics: Metrics,
+}
+
+impl Directory {
+    fn insert(&mut self, components: &[&str], index: usize, metrics: Metrics) {
+        self.metrics += metrics;
+        match components {
+            [] => {}
+            [name] => {
+                self.files.insert(name.to_string(), index);
+            }
+            [name, rest @ ..] => self
+                .directories
+                .entry(name.to_string())
+                .or_default()
+                .insert(rest, index, metrics),
+        }
+    }
+
+    /// Add the nodes of the children in pre-order, directories first.
+    fn flatten(&self, lcov: &Lcov, depth: usize, nodes: &mut Vec<Node>) {
+        for (name, mut directory) in &self.directories {
+            // Directories that only contain another directory are shown as a single node.
+            let mut name = name.clone();
+            while directory.files.is_empty() && directory.directories.len() == 1 {
+                let (child_name, child) = directory.directories.iter().next().unwrap();
+                name = format!("{name}/{child_name}");
+                directory = child;
+            }
+
+            nodes.push(Node {
+                name,
+                depth,
+                kind: NodeKind::Directory,
+                metrics: directory.metrics,
+            });
+            directory.flatten(lcov, depth + 1, nodes);
+        }
+
+        for (name, &index) in &self.files {
+            nodes.push(Node {
+                name: name.clone(),
+                depth,
+                kind: NodeKind::File(index),
+                metrics: *lcov.files()[index].metrics(),
+            });
+        }
+    }
+}
+
+/// The lines of a source file that is opened in the source view.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Source {
+    /// The index of the file in the files of the LCOV file.
+    pub(crate) file: usize,
+    /// The text of every line, or empty lines when the source could not be read.
+    pub(crate) lines: Vec<String>,
+    /// The selected line, starting at 1.
+    pub(crate) cursor: usize,
+    /// The first line that is shown, starting at 0.
+    pub(crate) scroll: usize,
+    /// A message about the source, like that it was not found.
+    pub(crate) message: Option<String>,
+}
+
+/// The state of the terminal UI.
+pub(crate) struct App {
+    pub(crate) lcov: Lcov,
+    pub(crate) thresholds: Thresholds,
+    pub(crate) nodes: Vec<Node>,
+    /// The indices of the directories that are collapsed.
+    pub(crate) collapsed: HashSet<usize>,
+    /// The index of the selected node.
+    pub(crate) selected: usize,
+    pub(crate) metric: Metric,
+    /// The opened source file, if any.
+    pub(crate) source: Option<Source>,
+    /// The search query, while it is typed.
+    pub(crate) search: Option<String>,
+    /// The last search query.
+    pub(crate) query: String,
+    /// The number of rows of the list or source, as last drawn.
+    pub(crate) page: usize,
+    pub(crate) quit: bool,
+}
+
+impl App {
+    /// Create the state for an LCOV file, with paths shown relative to `root`.
+    pub(crate) fn new(lcov: Lcov, root: Option<&Path>, thresholds: Thresholds) -> Self {
+        let mut tree = Directory::default();
+        for (index, file) in lcov.files().iter().enumerate() {
+            let components: Vec<&str> = relative_path(file.name(), root)
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .collect();
+            tree.insert(&components, index, *file.metrics());
+        }
+
+        let mut nodes = vec![];
+        tree.flatten(&lcov, 0, &mut nodes);
+
+        Self {
+            lcov,
+            thresholds,
+            nodes,
+            collapsed: HashSet::new(),
+            selected: 0,
+            metric: Metric::Lines,
+            source: None,
+            search: None,
+            query: String::new(),
+            page: DEFAULT_PAGE,
+            quit: false,
+        }
+    }
+
+    /// Return the indices of the nodes that are not inside a collapsed directory.
+    pub(crate) fn visible(&self) -> Vec<usize> {
+        let mut visible = vec![];
+        let mut hidden_below: Option<usize> = None;
+        for (index, node) in self.nodes.iter().enumerate() {
+            match hidden_below {
+                Some(depth) if node.depth > depth => continue,
+                _ => hidden_below = None,
+            }
+            visible.push(index);
+            if self.collapsed.contains(&index) {
+                hidden_below = Some(node.depth);
+            }
+        }
+        visible
+    }
+
+    /// Return the file that is opened in the source view.
+    pub(crate) fn source_file(&self) -> Option<&LcovFile> {
+        self.source
+            .as_ref()
+            .map(|source| &self.lcov.files()[source.file])
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) {
+        if self.search.is_some() {
+            self.handle_search_key(key);
+        } else if self.source.is_some() {
+            self.handle_source_key(key);
+        } else {
+            self.handle_tree_key(key);
+        }
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(self.page as isize),
+            KeyCode::PageUp => self.move_selection(-(self.page as isize)),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.open(),
+            KeyCode::Left | KeyCode::Char('h') => self.close(),
+            KeyCode::Tab => self.next_metric(),
+            KeyCode::Char('/') => self.search = Some(String::new()),
+            KeyCode::Char('n') => self.find(&self.query.clone(), true),
+            KeyCode::Char('N') => self.find(&self.query.clone(), false),
+            _ => {}
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.search = None,
+            KeyCode::Enter => {
+                self.query = self.search.take().unwrap_or_default();
+            }
+            KeyCode::Backspace => {
+                search.pop();
+            }
+            KeyCode::Char(c) => {
+                search.push(c);
+                let search = search.clone();
+                if !self.nodes.is_empty() && !self.matches(self.selected, &search) {
+                    self.find(&search, true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_source_key(&mut self, key: KeyEvent) {
+        let Some(source) = &mut self.source else {
+            return;
+        };
+        let last = source.lines.len().max(1);
+        let page = self.page;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => {
+                self.source = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => source.cursor = (source.cursor + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => {
+                source.cursor = source.cursor.saturating_sub(1).max(1)
+            }
+            KeyCode::PageDown => source.cursor = (source.cursor + page).min(last),
+            KeyCode::PageUp => source.cursor = source.cursor.saturating_sub(page).max(1),
+            KeyCode::Home | KeyCode::Char('g') => source.cursor = 1,
+            KeyCode::End | KeyCode::Char('G') => source.cursor = last,
+            KeyCode::Char('n') => self.jump_uncovered(true),
+            KeyCode::Char('N') => self.jump_uncovered(false),
+            KeyCode::Tab => self.next_metric(),
+            _ => {}
+        }
+    }
+
+    fn next_metric(&mut self) {
+        self.metric = match self.metric {
+            Metric::Lines => Metric::Functions,
+            Metric::Functions => Metric::Branches,
+            Metric::Branches => Metric::Lines,
+        };
+    }
+
+    /// Move the selection by a number of visible rows, clamped to the first and last row.
+    fn move_selection(&mut self, rows: isize) {
+        let visible = self.visible();
+        let Some(position) = visible.iter().position(|&index| index == self.selected) else {
+            return;
+        };
+        let position = position.saturating_add_signed(rows).min(visible.len() - 1);
+        self.selected = visible[position];
+    }
+
+    /// Expand or collapse the selected directory, or open the selected source file.
+    fn open(&mut self) {
+        let Some(node) = self.nodes.get(self.selected) else {
+            return;
+        };
+        match node.kind {
+            NodeKind::Directory => {
+                if !self.collapsed.remove(&self.selected) {
+                    self.collapsed.insert(self.selected);
+                }
+            }
+            NodeKind::File(file) => self.open_source(file),
+        }
+    }
+
+    /// Collapse the selected directory, or select the parent directory.
+    fn close(&mut self) {
+        let Some(node) = self.nodes.get(self.selected) else {
+            return;
+        };
+        if node.kind == NodeKind::Directory && !self.collapsed.contains(&self.selected) {
+            self.collapsed.insert(self.selected);
+        } else if let Some(parent) = self.parent(self.selected) {
+            self.selected = parent;
+        }
+    }
+
+    fn parent(&self, index: usize) -> Option<usize> {
+        let depth = self.nodes[index].depth;
+        (0..index).rev().find(|&i| self.nodes[i].depth < depth)
+    }
+
+    fn open_source(&mut self, file: usize) {
+        let lcov_file = &self.lcov.files()[file];
+        let (lines, message) = match std::fs::read_to_string(lcov_file.name()) {
+            Ok(text) => (text.lines().map(String::from).collect(), None),
+            Err(err) => {
+                let last = lcov_file.line_hits().keys().last().copied().unwrap_or(0);
+                (
+                    vec![String::new(); last],
+                    Some(format!("Could not read {}: {err}", lcov_file.name())),
+                )
+            }
+        };
+
+        self.source = Some(Source {
+            file,
+            lines,
+            cursor: 1,
+            scroll: 0,
+            message,
+        });
+    }
+
+    fn matches(&self, index: usize, query: &str) -> bool {
+        let Some(node) = self.nodes.get(index) else {
+            return false;
+        };
+        !query.is_empty() && node.name.to_lowercase().contains(&query.to_lowercase())
+    }
+
+    /// Select the next or previous node whose name contains the query, wrapping around, and
+    /// expand the directories around it.
+    fn find(&mut self, query: &str, forward: bool) {
+        let len = self.nodes.len();
+        let found = (1..=len)
+            .map(|offset| {
+                if forward {
+                    (self.selected + offset) % len
+                } else {
+                    (self.selected + len - offset) % len
+                }
+            })
+            .find(|&index| self.matches(index, query));
+
+        if let Some(index) = found {
+            let mut ancestor = index;
+            while let Some(parent) = self.parent(ancestor) {
+                self.collapsed.remove(&parent);
+                ancestor = parent;
+            }
+            self.selected = index;
+        }
+    }
+
+    /// Return the first lines of the regions that are not covered for the current metric.
+    pub(crate) fn uncovered_lines(&self) -> Vec<usize> {
+        let Some(file) = self.source_file() else {
+            return vec![];
+        };
+
+        match self.metric {
+            Metric::Lines => file
+                .uncovered_ranges(|_| true)
+                .into_iter()
+                .map(|range| *range.start())
+                .collect(),
+            Metric::Functions => {
+                let mut lines: Vec<usize> = file
+                    .function_hits()
+                    .iter()
+                    .filter(|&(_, &hits)| hits == 0)
+                    .filter_map(|(name, _)| file.function_lines().get(name).copied())
+                    .collect();
+                lines.sort_unstable();
+                lines.dedup();
+                lines
+            }
+            Metric::Branches => {
+                let mut lines: Vec<usize> = file
+                    .branch_hits()
+                    .iter()
+                    .filter(|&(_, &taken)| taken.unwrap_or(0) == 0)
+                    .map(|(&(line, _, _), _)| line)
+                    .collect();
+                lines.dedup();
+                lines
+            }
+        }
+    }
+
+    /// Move the cursor to the next or previous uncovered region.
+    fn jump_uncovered(&mut self, forward: bool) {
+        let lines = self.uncovered_lines();
+        let Some(source) = &mut self.source else {
+            return;
+        };
+        let target = if forward {
+            lines.into_iter().find(|&line| line > source.cursor)
+        } else {
+            lines.into_iter().rev().find(|&line| line < source.cursor)
+        };
+        if let Some(line) = target {
+            source.cursor = line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let input = "\
+SF:/repo/src/iface/a.rs
+FN:2,_RNvCs123_4test1a
+FNDA:0,_RNvCs123_4test1a
+DA:1,1
+DA:2,0
+DA:3,0
+DA:5,1
+DA:6,0
+LF:5
+LH:2
+end_of_record
+SF:/repo/src/iface/b.rs
+DA:1,1
+LF:1
+LH:1
+end_of_record
+SF:/repo/src/lib.rs
+DA:1,0
+LF:1
+LH:0
+end_of_record
+";
+        let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        App::new(lcov, Some(Path::new("/repo")), Thresholds::default())
+    }
+
+    fn names(app: &App) -> Vec<&str> {
+        app.visible()
+            .into_iter()
+            .map(|index| app.nodes[index].name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut app = app();
+        assert_eq!(names(&app), ["src", "iface", "a.rs", "b.rs", "lib.rs"]);
+        assert_eq!(app.nodes[0].metrics.lines.hit, 3);
+        assert_eq!(app.nodes[1].metrics.lines.found, 6);
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Left.into());
+        assert_eq!(names(&app), ["src", "iface", "lib.rs"]);
+
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(app.nodes[app.selected].name, "lib.rs");
+        app.handle_key(KeyCode::Left.into());
+        assert_eq!(app.nodes[app.selected].name, "src");
+    }
+
+    #[test]
+    fn test_search_empty() {
+        let lcov = Lcov::from_reader("".as_bytes()).unwrap();
+        let mut app = App::new(lcov, None, Thresholds::default());
+        assert!(app.nodes.is_empty());
+
+        for key in [KeyCode::Char('/'), KeyCode::Char('a'), KeyCode::Enter] {
+            app.handle_key(key.into());
+        }
+        app.handle_key(KeyCode::Char('n').into());
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.query, "a");
+        assert!(app.source.is_none());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut app = app();
+        app.collapsed.insert(1);
+
+        for key in [KeyCode::Char('/'), KeyCode::Char('B'), KeyCode::Enter] {
+            app.handle_key(key.into());
+        }
+        assert_eq!(app.nodes[app.selected].name, "b.rs");
+        assert!(app.collapsed.is_empty());
+
+        app.query = "rs".to_string();
+        app.handle_key(KeyCode::Char('n').into());
+        assert_eq!(app.nodes[app.selected].name, "lib.rs");
+        app.handle_key(KeyCode::Char('n').into());
+        assert_eq!(app.nodes[app.selected].name, "a.rs");
+    }
+
+    #[test]
+    fn test_jump_uncovered() {
+        let mut app = app();
+        app.selected = 2;
+        app.handle_key(KeyCode::Enter.into());
+        assert!(app.source.is_some());
+
+        let cursor = |app: &App| app.source.as_ref().unwrap().cursor;
+        assert_eq!(app.uncovered_lines(), [2, 6]);
+        app.handle_key(KeyCode::Char('n').into());
+        assert_eq!(cursor(&app), 2);
+        app.handle_key(KeyCode::Char('n').into());
+        assert_eq!(cursor(&app), 6);
+        app.handle_key(KeyCode::Char('N').into());
+        assert_eq!(cursor(&app), 2);
+
+        app.handle_key(KeyCode::Tab.into());
+        assert_eq!(app.metric, Metric::Functions);
+        assert_eq!(app.uncovered_lines(), [2]);
+
+        app.handle_key(KeyCode::Esc.into());
+        assert!(app.source.is_none());
+        assert!(!app.quit);
+    }
+}
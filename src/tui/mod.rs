@@ -0,0 +1,53 @@
+//! A full-screen terminal browser for the coverage of an LCOV file.
+
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+
+use crate::report::Thresholds;
+use crate::Lcov;
+
+mod app;
+mod ui;
+
+use self::app::App;
+
+/// Browse the coverage of an LCOV file in the terminal, until the user quits.
+///
+/// The source files are shown as a tree of directories relative to `root`, with a coverage bar
+/// per directory and file. A file opens in a source view with the hit count of every line. Source
+/// files are read from the paths of their `SF:` records.
+///
+/// Fails if stdin or stdout is not a terminal, or if the terminal cannot be set up.
+pub fn run(lcov: Lcov, root: Option<&Path>, thresholds: Thresholds) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        bail!("the terminal browser needs stdin and stdout to be a terminal");
+    }
+    let mut app = App::new(lcov, root, thresholds);
+
+    let mut terminal = match ratatui::try_init() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            ratatui::restore();
+            return Err(err.into());
+        }
+    };
+    let result = run_app(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key);
+            }
+        }
+    }
+    Ok(())
+}
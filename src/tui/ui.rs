@@ -0,0 +1,244 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use super::app::{App, NodeKind};
+use crate::metric::{Metric, Percentage};
+use crate::report::Level;
+use crate::LcovFile;
+
+/// The width of a coverage bar, in characters.
+const BAR_WIDTH: usize = 20;
+
+pub(crate) fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+    // The borders of the block take two rows.
+    app.page = main.height.saturating_sub(2).max(1) as usize;
+
+    if app.source.is_some() {
+        draw_source(frame, app, main);
+    } else {
+        draw_tree(frame, app, main);
+    }
+    draw_status(frame, app, status);
+}
+
+fn draw_tree(frame: &mut Frame, app: &App, area: Rect) {
+    let visible = app.visible();
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|&index| {
+            let node = &app.nodes[index];
+            let counter = node.metrics.get(app.metric);
+            let marker = match node.kind {
+                NodeKind::Directory if app.collapsed.contains(&index) => "▸ ",
+                NodeKind::Directory => "▾ ",
+                NodeKind::File(_) => "  ",
+            };
+            let name = format!("{}{marker}{}", "  ".repeat(node.depth), node.name);
+            let name = match node.kind {
+                NodeKind::Directory => Span::from(name).bold(),
+                NodeKind::File(_) => Span::from(name),
+            };
+
+            Row::new(vec![
+                Line::from(name),
+                bar(app, counter.percentage()),
+                Line::from(counter.percentage().to_string()).right_aligned(),
+                Line::from(format!("{}/{}", counter.hit, counter.found)).right_aligned(),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(BAR_WIDTH as u16),
+            Constraint::Length(8),
+            Constraint::Length(15),
+        ],
+    )
+    .header(Row::new(["Name", "", "H/T", "Hit/Total"]).bold())
+    .block(Block::bordered().title(format!(" {} ", app.lcov.name().display())))
+    .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+
+    let mut state = TableState::default()
+        .with_selected(visible.iter().position(|&index| index == app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_source(frame: &mut Frame, app: &mut App, area: Rect) {
+    let page = app.page;
+    let Some(source) = &mut app.source else {
+        return;
+    };
+
+    // Keep the cursor on the screen.
+    if source.cursor <= source.scroll {
+        source.scroll = source.cursor.saturating_sub(1);
+    } else if source.cursor > source.scroll + page {
+        source.scroll = source.cursor - page;
+    }
+    let (scroll, cursor) = (source.scroll, source.cursor);
+
+    let file = &app.lcov.files()[source.file];
+    let source = app.source.as_ref().unwrap();
+    let lines: Vec<Line> = source
+        .lines
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(page)
+        .map(|(i, text)| {
+            let number = i + 1;
+            let (count, style) = gutter(app, file, number);
+            let mut line = Line::from(vec![
+                Span::from(format!("{number:>6} ")).dark_gray(),
+                Span::styled(format!("{count:>8} "), style),
+                Span::from("│ ").dark_gray(),
+                Span::from(text.replace('\t', "    ")),
+            ]);
+            if number == cursor {
+                line = line.add_modifier(Modifier::REVERSED);
+            }
+            line
+        })
+        .collect();
+
+    let mut title = format!(" {} ", file.display_name());
+    if let Some(message) = &source.message {
+        title += &format!("({message}) ");
+    }
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+/// Return the count to show next to a line for the current metric, and its style.
+fn gutter(app: &App, file: &LcovFile, line: usize) -> (String, Style) {
+    let covered = Style::new().fg(Color::Green);
+    let uncovered = Style::new().fg(Color::Red);
+
+    match app.metric {
+        Metric::Lines => match file.line_hits().get(&line) {
+            Some(0) => ("0".to_string(), uncovered),
+            Some(hits) => (hits.to_string(), covered),
+            None => (String::new(), Style::new()),
+        },
+        Metric::Functions => {
+            let hits: Vec<usize> = file
+                .function_lines()
+                .iter()
+                .filter(|&(_, &start)| start == line)
+                .filter_map(|(name, _)| file.function_hits().get(name).copied())
+                .collect();
+            match hits.iter().max() {
+                None => (String::new(), Style::new()),
+                Some(0) => ("0".to_string(), uncovered),
+                Some(hits) => (hits.to_string(), covered),
+            }
+        }
+        Metric::Branches => {
+            let branches: Vec<Option<usize>> = file
+                .branch_hits()
+                .range(
+                    (line, String::new(), String::new())..(line + 1, String::new(), String::new()),
+                )
+                .map(|(_, &taken)| taken)
+                .collect();
+            let taken = branches
+                .iter()
+                .filter(|taken| taken.unwrap_or(0) > 0)
+                .count();
+            let style = if branches.is_empty() {
+                Style::new()
+            } else if taken == branches.len() {
+                covered
+            } else if taken == 0 {
+                uncovered
+            } else {
+                Style::new().fg(Color::Yellow)
+            };
+            match branches.len() {
+                0 => (String::new(), style),
+                found => (format!("{taken}/{found}"), style),
+            }
+        }
+    }
+}
+
+/// Draw a coverage bar, colored by the thresholds.
+fn bar(app: &App, percentage: Percentage) -> Line<'static> {
+    let Some(value) = percentage.value() else {
+        return Line::from("");
+    };
+    let filled = ((value / 100. * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    let color = match app.thresholds.level(percentage) {
+        Some(Level::Low) => Color::Red,
+        Some(Level::Medium) => Color::Yellow,
+        Some(Level::High) | None => Color::Green,
+    };
+
+    Line::from(vec![
+        Span::from("█".repeat(filled)).fg(color),
+        Span::from("░".repeat(BAR_WIDTH - filled)).dark_gray(),
+    ])
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let text = if let Some(search) = &app.search {
+        format!("/{search}")
+    } else if app.source.is_some() {
+        format!(
+            " {} │ ↑↓ move  n/N next/previous uncovered  Tab metric  Esc back",
+            app.metric.name()
+        )
+    } else {
+        format!(
+            " {} │ ↑↓ move  Enter open  ← close  / search  n/N next/previous match  Tab metric  q quit",
+            app.metric.name()
+        )
+    };
+    frame.render_widget(Paragraph::new(text).reversed(), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyCode;
+    use ratatui::Terminal;
+
+    use super::*;
+    use crate::report::Thresholds;
+    use crate::Lcov;
+
+    #[test]
+    fn test_draw() {
+        let input = "SF:/repo/src/a.rs\nDA:1,1\nDA:2,0\nLF:2\nLH:1\nend_of_record\n";
+        let lcov = Lcov::from_reader(input.as_bytes()).unwrap();
+        let mut app = App::new(lcov, Some(Path::new("/repo")), Thresholds::default());
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("▾ src"));
+        assert!(screen.contains("50.00%"));
+        assert!(screen.contains("1/2"));
+        assert_eq!(app.page, 7);
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Enter.into());
+        terminal.draw(|frame| draw(frame, &mut app)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("src/a.rs"));
+        assert!(screen.contains("     2        0 │"));
+    }
+}